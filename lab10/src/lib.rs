@@ -0,0 +1,85 @@
+use axum::{
+    extract::rejection::{JsonRejection, PathRejection}, http::{StatusCode, Uri}, response::{IntoResponse, Response},
+    routing::{get, post}, Json, Router, extract::Path
+};
+
+
+#[derive(serde::Deserialize)]
+struct InputGreeting {
+    name: String,
+    #[serde(alias = "input")]
+    greet: String,
+}
+
+#[derive(serde::Serialize)]
+struct Greeting {
+    name: String,
+    greet: String,
+}
+
+// Every error is answered with its status and a JSON body { "error": reason, "status": code }
+struct ApiError {
+    status: StatusCode,
+    error: String,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "error": self.error, "status": self.status.as_u16() });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+// The status of a rejected body is the one axum gives it, e.g. 415 without a JSON content type or 422 for a missing field
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> ApiError {
+        ApiError { status: rejection.status(), error: rejection.body_text() }
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> ApiError {
+        ApiError { status: rejection.status(), error: rejection.body_text() }
+    }
+}
+
+// build our application with its routes, kept apart from main so that the tests can call it without a server
+pub fn app() -> Router {
+    Router::new()
+    .route("/hello", get(hello_handler).fallback(method_not_allowed))
+    .route("/greet/:name", get(greet_handler).fallback(method_not_allowed))
+    .route("/greetme", post(greetme_handler).fallback(method_not_allowed))
+    .fallback(not_found)
+}
+
+
+async fn hello_handler() -> Json<Greeting> {
+    Json(Greeting {
+        name: "World".to_owned(),
+        greet: "Hello".to_owned(),
+    })
+}
+
+async fn greet_handler(name: Result<Path<String>, PathRejection>) -> Result<Json<Greeting>, ApiError> { //Extracts the parameter from the URI
+    let Path(name) = name?;
+    Ok(Json(Greeting {
+        name,
+        greet: "Hello".to_owned(),
+    }))
+}
+
+async fn greetme_handler(input: Result<Json<InputGreeting>, JsonRejection>) -> Result<Json<Greeting>, ApiError> {
+    let Json(input) = input?;
+    Ok(Json(Greeting {
+        name: input.name,
+        greet: input.greet,
+    }))
+}
+
+async fn not_found(uri: Uri) -> ApiError {
+    ApiError { status: StatusCode::NOT_FOUND, error: format!("404 page not found! Nothing to see here at {}", uri.path()) }
+}
+
+async fn method_not_allowed(uri: Uri) -> ApiError {
+    ApiError { status: StatusCode::METHOD_NOT_ALLOWED, error: format!("{} does not accept this method", uri.path()) }
+}
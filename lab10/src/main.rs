@@ -2,67 +2,108 @@
  working with I/O-bound tasks, such as a server in our case.
  An async runtime uses a small amount of (expensive) threads to handle a large amount of (cheap) tasks.
 */
-use axum::{
-    extract::rejection::JsonRejection, http::StatusCode, routing::{get, post}, Json, Router, extract::Path
-};
+use std::{env, io::ErrorKind, process};
 
+const DEFAULT_HOST: &str = "0.0.0.0";
+const DEFAULT_PORT: u16 = 3000;
 
-#[derive(serde::Deserialize)]
-struct InputGreeting {
-    name: String,
-    #[serde(alias = "input")]
-    greet: String,
-}
-
-#[derive(serde::Serialize)]
-struct Greeting {
-    name: String,
-    greet: String,
-}
 
 #[tokio::main]
 async fn main() { 
-    // build our application with a single route
-    let app = Router::new()
-    .route("/hello", get(hello_handler))
-    .route("/greet/:name", get(greet_handler))
-    .route("/greetme", post(greetme_handler))
-    .fallback(|| async {
-        (StatusCode::NOT_FOUND, "404 page not found! Nothing to see here!")
-    });
-
-    
-    // run our app with hyper, listening globally on port 3000
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
-}
-
+    let app = lab10::app();
 
-async fn hello_handler() -> &'static str {
-    "Hello, World!"
-}
+    let (host, port) = match address(env::args().skip(1), env::var("HOST").ok(), env::var("PORT").ok()) {
+        Ok(address) => address,
+        Err(message) => {
+            eprintln!("lab10: {}", message);
+            eprintln!("usage: lab10 [--host HOST] [--port PORT], or the HOST and PORT environment variables");
+            process::exit(2);
+        }
+    };
 
-async fn greet_handler(Path(name): Path<String>) -> String { //Extracts the parameter from the URI
-    let greeting = Greeting {
-        name,
-        greet: "Hello".to_owned(),
+    // run our app with hyper, port 0 lets the OS pick a free port
+    let listener = match tokio::net::TcpListener::bind((host.as_str(), port)).await {
+        Ok(listener) => listener,
+        Err(e) if e.kind() == ErrorKind::AddrInUse => {
+            eprintln!("lab10: port {} on {} is already in use, pick another one with --port or PORT", port, host);
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("lab10: could not listen on {}:{}: {}", host, port, e);
+            process::exit(1);
+        }
     };
+    match listener.local_addr() {
+        Ok(address) => println!("listening on http://{}", address),
+        Err(_) => println!("listening on http://{}:{}", host, port),
+    }
 
-    match serde_json::to_string(&greeting) {
-        Ok(greeting) => greeting,
-        Err(_) => (StatusCode::BAD_REQUEST).to_string(),
+    if let Err(e) = axum::serve(listener, app).await {
+        eprintln!("lab10: {}", e);
+        process::exit(1);
     }
 }
 
-async fn greetme_handler(input: Result<Json<InputGreeting>, JsonRejection>) -> Result<Json<Greeting>, StatusCode> {
-    let input = match input {
-        Ok(input) => Ok(Json(Greeting {
-            name: input.name.clone(),
-            greet: input.greet.clone(),
-        })),
-        Err(_) => Err(StatusCode::BAD_REQUEST)
+// Host and port from --host and --port, falling back to the given HOST and PORT environment values and then the defaults
+fn address(mut args: impl Iterator<Item = String>, mut host: Option<String>, mut port: Option<String>) -> Result<(String, u16), String> {
+    while let Some(arg) = args.next() {
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_owned(), Some(value.to_owned())),
+            None => (arg, None),
+        };
+        let target = match flag.as_str() {
+            "--host" => &mut host,
+            "--port" => &mut port,
+            _ => return Err(format!("unknown argument {}", flag)),
+        };
+        match value.or_else(|| args.next()) {
+            Some(value) => *target = Some(value),
+            None => return Err(format!("{} needs a value", flag)),
+        }
+    }
+
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| format!("invalid port {}, expected a number from 0 to 65535", port))?,
+        None => DEFAULT_PORT,
     };
-    input
+    Ok((host.unwrap_or_else(|| DEFAULT_HOST.to_owned()), port))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    fn parse(args: &[&str], host: Option<&str>, port: Option<&str>) -> Result<(String, u16), String> {
+        address(args.iter().map(|arg| arg.to_string()), host.map(str::to_owned), port.map(str::to_owned))
+    }
+
+    #[test]
+    fn defaults_without_flags_or_environment() {
+        assert_eq!(parse(&[], None, None), Ok((DEFAULT_HOST.to_owned(), DEFAULT_PORT)));
+    }
+
+    #[test]
+    fn flags_take_precedence_over_the_environment() {
+        assert_eq!(parse(&[], Some("127.0.0.1"), Some("8080")), Ok(("127.0.0.1".to_owned(), 8080)));
+        assert_eq!(parse(&["--host", "localhost", "--port=9000"], Some("127.0.0.1"), Some("8080")), Ok(("localhost".to_owned(), 9000)));
+    }
+
+    #[test]
+    fn port_zero_is_accepted() {
+        assert_eq!(parse(&["--port=0"], None, None), Ok((DEFAULT_HOST.to_owned(), 0)));
+    }
+
+    #[test]
+    fn flags_need_a_value() {
+        assert_eq!(parse(&["--port"], None, None), Err("--port needs a value".to_owned()));
+        assert_eq!(parse(&["--verbose"], None, None), Err("unknown argument --verbose".to_owned()));
+    }
+
+    #[test]
+    fn rejects_ports_that_are_not_numbers_or_out_of_range() {
+        for port in ["http", "-1", "65536"] {
+            assert_eq!(parse(&["--port", port], None, None), Err(format!("invalid port {}, expected a number from 0 to 65535", port)));
+        }
+        assert!(parse(&[], None, Some("abc")).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+// Drives the router in-process, without binding a port
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+// The status and JSON body of the response, which always has to be sent as JSON
+async fn send(request: Request<Body>) -> (StatusCode, Value) {
+    let response = lab10::app().oneshot(request).await.unwrap();
+    let status = response.status();
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn get(uri: &str) -> (StatusCode, Value) {
+    send(Request::get(uri).body(Body::empty()).unwrap()).await
+}
+
+async fn post_json(uri: &str, body: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_owned()))
+        .unwrap();
+    send(request).await
+}
+
+// The reason given in the error body, checked to come with the status
+fn assert_error(response: (StatusCode, Value), status: StatusCode) -> String {
+    let (actual, body) = response;
+    assert_eq!(actual, status, "{}", body);
+    assert_eq!(body["status"], status.as_u16());
+    let error = body["error"].as_str().unwrap_or_default();
+    assert!(!error.is_empty(), "{}", body);
+    error.to_owned()
+}
+
+#[tokio::test]
+async fn hello_says_hello() {
+    assert_eq!(get("/hello").await, (StatusCode::OK, json!({ "name": "World", "greet": "Hello" })));
+}
+
+#[tokio::test]
+async fn greet_greets_the_name_in_the_path() {
+    assert_eq!(get("/greet/Ada").await, (StatusCode::OK, json!({ "name": "Ada", "greet": "Hello" })));
+}
+
+#[tokio::test]
+async fn greetme_echoes_the_greeting() {
+    let response = post_json("/greetme", r#"{ "name": "Ada", "greet": "Hi" }"#).await;
+    assert_eq!(response, (StatusCode::OK, json!({ "name": "Ada", "greet": "Hi" })));
+}
+
+#[tokio::test]
+async fn greetme_accepts_input_for_greet() {
+    let response = post_json("/greetme", r#"{ "name": "Ada", "input": "Hey" }"#).await;
+    assert_eq!(response, (StatusCode::OK, json!({ "name": "Ada", "greet": "Hey" })));
+}
+
+#[tokio::test]
+async fn greetme_rejects_malformed_json() {
+    for body in [r#"{ "name": "Ada", "#, ""] {
+        assert_error(post_json("/greetme", body).await, StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn greetme_rejects_json_that_is_not_a_greeting() {
+    for body in [r#"{ "name": "Ada" }"#, r#"[1, 2]"#, r#"{ "name": 1, "greet": "Hi" }"#] {
+        assert_error(post_json("/greetme", body).await, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    let error = assert_error(post_json("/greetme", r#"{ "name": "Ada" }"#).await, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(error.contains("missing field `greet`"), "{}", error);
+}
+
+#[tokio::test]
+async fn unknown_paths_are_named_in_the_error() {
+    let error = assert_error(get("/nothing").await, StatusCode::NOT_FOUND);
+    assert!(error.contains("/nothing"), "{}", error);
+}
+
+#[tokio::test]
+async fn greetme_rejects_a_body_that_is_not_json() {
+    let request = Request::post("/greetme").body(Body::from(r#"{ "name": "Ada", "greet": "Hi" }"#)).unwrap();
+    assert_error(send(request).await, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn unknown_paths_are_not_found() {
+    for uri in ["/", "/nothing", "/greet", "/greet/Ada/again"] {
+        assert_error(get(uri).await, StatusCode::NOT_FOUND);
+    }
+}
+
+#[tokio::test]
+async fn wrong_methods_are_refused() {
+    assert_error(get("/greetme").await, StatusCode::METHOD_NOT_ALLOWED);
+    let request = Request::post("/hello").body(Body::empty()).unwrap();
+    assert_error(send(request).await, StatusCode::METHOD_NOT_ALLOWED);
+}
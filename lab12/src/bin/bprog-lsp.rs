@@ -0,0 +1,18 @@
+//Language server for bprog, run by the editor and talking LSP over standard input and output
+use std::io;
+use std::process;
+
+fn main() {
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+
+    match lab12::lsp::serve(&mut input, &mut output) {
+        Ok(true) => (),
+        //Exiting without a shutdown request first is an error by the protocol
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("bprog-lsp: {}", e);
+            process::exit(1);
+        },
+    }
+}
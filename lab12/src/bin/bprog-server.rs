@@ -0,0 +1,25 @@
+//HTTP server running bprog programs sent to `POST /eval`, listening on the address given as argument
+use std::env;
+use std::process;
+
+use lab12::server::{serve, DEFAULT_LIMITS};
+
+#[tokio::main]
+async fn main() {
+    let address = env::args().nth(1).unwrap_or_else(|| "127.0.0.1:3000".to_string());
+
+    let listener = match tokio::net::TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("bprog-server: could not listen on {}: {}", address, e);
+            process::exit(1);
+        },
+    };
+    let address = listener.local_addr().map(|address| address.to_string()).unwrap_or(address);
+    println!("bprog-server listening on http://{}", address);
+
+    if let Err(e) = serve(listener, DEFAULT_LIMITS).await {
+        eprintln!("bprog-server: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,83 @@
+//Stack effect and description of every builtin word, shown by the language server
+pub struct Builtin {
+    pub name: &'static str,
+    pub effect: &'static str,
+    pub description: &'static str,
+}
+
+pub const BUILTINS : &[Builtin] = &[
+    Builtin { name: "+", effect: "( x y -- x+y )", description: "Adds two numbers." },
+    Builtin { name: "-", effect: "( x y -- x-y )", description: "Subtracts the top number from the one below it." },
+    Builtin { name: "*", effect: "( x y -- x*y )", description: "Multiplies two numbers." },
+    Builtin { name: "/", effect: "( x y -- x/y )", description: "Divides x by y, giving an exact rational when either is a rational and a float otherwise." },
+    Builtin { name: "div", effect: "( x y -- x div y )", description: "Division of x by y rounded down, an int for two ints and a whole float otherwise." },
+    Builtin { name: "pow", effect: "( x y -- x^y )", description: "x to the power of y, an int for two ints when y is not negative and the result fits." },
+    Builtin { name: "min", effect: "( x y -- n ) or ( list -- n )", description: "The smaller of two numbers, or the smallest number of a non-empty list." },
+    Builtin { name: "max", effect: "( x y -- n ) or ( list -- n )", description: "The larger of two numbers, or the largest number of a non-empty list." },
+    Builtin { name: "abs", effect: "( x -- n )", description: "The absolute value of a number." },
+    Builtin { name: "floor", effect: "( x -- int )", description: "Rounds a number down to an int." },
+    Builtin { name: "ceil", effect: "( x -- int )", description: "Rounds a number up to an int." },
+    Builtin { name: "round", effect: "( x -- int )", description: "Rounds a number to the nearest int, halfway cases away from zero." },
+    Builtin { name: "sqrt", effect: "( x -- float )", description: "The square root of a number." },
+    Builtin { name: "toInt", effect: "( x -- int )", description: "Converts a number to an int, dropping the fraction." },
+    Builtin { name: "toFloat", effect: "( x -- float )", description: "Converts a number to a float." },
+    Builtin { name: "numerator", effect: "( x -- int )", description: "The numerator of a rational in lowest terms, an int is its own numerator." },
+    Builtin { name: "denominator", effect: "( x -- int )", description: "The denominator of a rational in lowest terms, 1 for an int." },
+    Builtin { name: "precision", effect: "( n -- )", description: "Prints floats with n decimals from now on, at least one. Floats rounded to fewer decimals than they have do not read back as the same value." },
+    Builtin { name: "<", effect: "( x y -- bool )", description: "True if x is less than y. Numbers, strings, lists and booleans can be compared with their own kind." },
+    Builtin { name: ">", effect: "( x y -- bool )", description: "True if x is greater than y." },
+    Builtin { name: "<=", effect: "( x y -- bool )", description: "True if x is less than or equal to y." },
+    Builtin { name: ">=", effect: "( x y -- bool )", description: "True if x is greater than or equal to y." },
+    Builtin { name: "==", effect: "( x y -- bool )", description: "True if x and y are structurally equal, values of different kinds are never equal." },
+    Builtin { name: "!=", effect: "( x y -- bool )", description: "True if x and y are not equal." },
+    Builtin { name: "&&", effect: "( bool bool -- bool )", description: "Logical and." },
+    Builtin { name: "||", effect: "( bool bool -- bool )", description: "Logical or." },
+    Builtin { name: "not", effect: "( bool -- bool )", description: "Logical negation." },
+    Builtin { name: "swap", effect: "( x y -- y x )", description: "Swaps the two top elements." },
+    Builtin { name: "pop", effect: "( x -- )", description: "Removes the top element." },
+    Builtin { name: "dup", effect: "( x -- x x )", description: "Duplicates the top element." },
+    Builtin { name: "over", effect: "( x y -- x y x )", description: "Copies the second element to the top." },
+    Builtin { name: "rot", effect: "( x y z -- y z x )", description: "Moves the third element to the top." },
+    Builtin { name: "nip", effect: "( x y -- y )", description: "Removes the second element." },
+    Builtin { name: "tuck", effect: "( x y -- y x y )", description: "Copies the top element below the second." },
+    Builtin { name: "2dup", effect: "( x y -- x y x y )", description: "Duplicates the two top elements." },
+    Builtin { name: "depth", effect: "( -- n )", description: "The number of elements on the stack." },
+    Builtin { name: "clear", effect: "( ... -- )", description: "Removes every element from the stack." },
+    Builtin { name: "length", effect: "( list|string|quotation -- int )", description: "Number of elements, characters or words." },
+    Builtin { name: "splitWords", effect: "( string -- list )", description: "Splits a string on spaces." },
+    Builtin { name: "parseInteger", effect: "( string -- int )", description: "Parses a string as an integer." },
+    Builtin { name: "parseFloat", effect: "( string -- float )", description: "Parses a string as a float." },
+    Builtin { name: "empty", effect: "( list -- bool )", description: "True if the list has no elements." },
+    Builtin { name: "head", effect: "( list -- x )", description: "First element of a list." },
+    Builtin { name: "tail", effect: "( list -- list )", description: "The list without its first element." },
+    Builtin { name: "cons", effect: "( x list -- list )", description: "Puts x in front of the list." },
+    Builtin { name: "append", effect: "( list list -- list )", description: "Joins two lists." },
+    Builtin { name: "sort", effect: "( list -- list )", description: "Sorts a list in ascending order, values of different kinds are grouped by kind and records ordered by type and fields, not by the == of their type." },
+    Builtin { name: "dedup", effect: "( list -- list )", description: "Removes repeated elements, keeping the first of each. Records are compared with the == of their type when it has one." },
+    Builtin { name: "exec", effect: "( quotation|'word -- ... )", description: "Executes a quotation or calls a quoted word. A quotation starting with `| a b |` first pops its arguments into local names." },
+    Builtin { name: "map", effect: "( list -- list ) map quotation|'word", description: "Applies the following quotation to every element, lazily for a sequence." },
+    Builtin { name: "pmap", effect: "( list -- list ) pmap quotation|'word", description: "Like map, with the elements run in parallel. The results keep the order of the list, and the quotation may not import, define functions or set the precision." },
+    Builtin { name: "pfilter", effect: "( list -- list ) pfilter quotation|'word", description: "Like filter, with the predicate run on the elements in parallel under the same rules as pmap." },
+    Builtin { name: "filter", effect: "( list -- list ) filter quotation|'word", description: "Keeps the elements the following predicate is True for, lazily for a sequence." },
+    Builtin { name: "take", effect: "( list n -- list )", description: "The first n elements of a list, or a sequence ending after n elements." },
+    Builtin { name: "takeWhile", effect: "( list -- list ) takeWhile quotation|'word", description: "The elements before the first one the following predicate is False for, lazily for a sequence." },
+    Builtin { name: "iterate", effect: "( x -- sequence ) iterate quotation|'word", description: "The endless lazy sequence x, f(x), f(f(x)), ... for the following quotation f." },
+    Builtin { name: "toList", effect: "( sequence -- list )", description: "Runs a lazy sequence and collects its elements, it has to be ended by take or takeWhile." },
+    Builtin { name: "each", effect: "( list -- ... ) each quotation|'word", description: "Runs the following quotation on every element, leaving the results on the stack." },
+    Builtin { name: "foldl", effect: "( list acc -- acc ) foldl quotation|'word", description: "Folds the list from the left with the following quotation." },
+    Builtin { name: "if", effect: "( bool -- ... ) if then else", description: "Runs the first of the two following quotations if true, otherwise the second." },
+    Builtin { name: "case", effect: "( x -- ... ) case { pattern branch ... }", description: "Runs the branch of the first pattern matching x. Patterns are values, the types int float rational bool string list quotation symbol sequence and record, `[ head | tail ]` binding the parts of a non-empty list, and `_` for anything. Values and list parts are consumed, a type or `_` leaves x on the stack." },
+    Builtin { name: "times", effect: "( n -- ... ) times quotation|'word", description: "Runs the following quotation n times." },
+    Builtin { name: "fun", effect: "( name|'name quotation -- )", description: "Defines a function called name, a quoted name also redefines an existing function." },
+    Builtin { name: "struct", effect: "( name|'name [ field ... ] -- )", description: "Defines a record type: the constructor name ( field ... -- record ) and an accessor name.field for every field. Functions like name.+, name.== and name.show define the operators + - * / == != and the printing of its records." },
+    Builtin { name: "record", effect: "( x ... 'name [ field ... ] -- record )", description: "A record of type name with the given fields, the values taken from the stack in order." },
+    Builtin { name: "field", effect: "( record 'field -- x )", description: "The value of a field of a record." },
+    Builtin { name: "import", effect: "( -- ) import \"file.bprog\"", description: "Loads the functions of a file into a namespace named after it." },
+    Builtin { name: "words", effect: "( -- list )", description: "The names of all builtins and functions, as symbols." },
+    Builtin { name: "help", effect: "( -- string ) help word", description: "The stack effect and description of the following builtin, or the body of a function." },
+    Builtin { name: "typeOf", effect: "( x -- string )", description: "The type of a value: int, float, rational, bool, string, list, quotation, symbol, sequence or record." },
+];
+
+pub fn builtin(name : &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
@@ -0,0 +1,101 @@
+//Pattern matching with `case`: `value case { 0 { " zero " } int { 1 + } [ h | t ] { t } _ { pop 0 } }`
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::lexer::tokenize;
+use crate::{datatype, quotation, Datatype, Env, Interpreter, ProgramError};
+
+pub enum Pattern {
+    //A value like `0`, `" yes "` or `[ ]`, the matched value is consumed
+    Literal(Datatype),
+    //A type name as given by `typeOf`, e.g. `int` or `list`, the matched value is left on the stack for the branch
+    Type(String),
+    //`[ head | tail ]` matches a non-empty list and binds its parts to local names
+    HeadTail(String, String),
+    //`_` matches anything and leaves it on the stack
+    Default,
+}
+
+impl Interpreter {
+    pub(crate) fn case(&mut self, mut value : Datatype, tokens : &mut Vec<&str>) -> Result<(), ProgramError> {
+        let branches = match quotation(tokens)? {
+            Datatype::Code(code, _) => code,
+            _ => return Err(ProgramError::ExpectedQuotation),
+        };
+        let mut branches : Vec<&str> = tokenize(&branches);
+        branches.reverse();
+
+        while !branches.is_empty() {
+            let pattern = pattern(&mut branches)?;
+            let body = self.read_quotation(&mut branches)?;
+
+            let bound = match self.matching(pattern, value)? {
+                Ok(bound) => bound,
+                Err(unmatched) => {
+                    value = unmatched;
+                    continue;
+                },
+            };
+            let body = match body {
+                Datatype::Code(code, mut env) if !bound.is_empty() => {
+                    env.retain(|(name, _)| bound.iter().all(|(bound, _)| bound != name));
+                    env.extend(bound);
+                    env.sort_by(|a, b| a.0.cmp(&b.0));
+                    Datatype::Code(code, env)
+                },
+                body => body,
+            };
+            return self.run_quotation(&body);
+        }
+        Err(ProgramError::NoMatch)
+    }
+
+    //Matches a value against a pattern, doing what the pattern does with it, and gives the names it binds for
+    //the branch. A value the pattern does not match is given back for the next one
+    pub(crate) fn matching(&mut self, pattern : Pattern, mut value : Datatype) -> Result<Result<Env, Datatype>, ProgramError> {
+        if let Pattern::Literal(literal) = &pattern {
+            return Ok(if self.equal(&value, literal)? { Ok(Vec::new()) } else { Err(value) });
+        }
+        let type_name = value.type_name();
+        match (pattern, &mut value) {
+            (Pattern::Type(name), _) if type_name == name => {
+                self.stack.push(value);
+                Ok(Ok(Vec::new()))
+            },
+            (Pattern::HeadTail(head, tail), Datatype::List(list)) if !list.is_empty() => {
+                //The value is not needed after the match, so its list is taken rather than copied
+                let mut list = core::mem::take(list);
+                self.spend(list.len() as u64)?;
+                let first = list.remove(0);
+                Ok(Ok(vec![(head, first), (tail, Datatype::List(list))]))
+            },
+            (Pattern::Default, _) => {
+                self.stack.push(value);
+                Ok(Ok(Vec::new()))
+            },
+            _ => Ok(Err(value)),
+        }
+    }
+}
+
+pub(crate) fn pattern(tokens : &mut Vec<&str>) -> Result<Pattern, ProgramError> {
+    let token = tokens.pop().ok_or(ProgramError::ExpectedQuotation)?;
+    match token {
+        "_" => return Ok(Pattern::Default),
+        "int" | "float" | "rational" | "bool" | "string" | "list" | "quotation" | "symbol" | "sequence" | "record" => return Ok(Pattern::Type(token.to_string())),
+        _ => (),
+    }
+
+    //The tokens are reversed, so `[ h | t ]` reads as `] t | h` from the end
+    if let ("[", [.., "]", tail, "|", head]) = (token, &tokens[..]) {
+        let pattern = Pattern::HeadTail(head.to_string(), tail.to_string());
+        tokens.truncate(tokens.len() - 4);
+        return Ok(pattern);
+    }
+
+    match datatype(token, tokens)? {
+        Some(value) => Ok(Pattern::Literal(value)),
+        None => Err(ProgramError::InvalidOperation),
+    }
+}
@@ -0,0 +1,175 @@
+//Structural equality and ordering of values, shared by the comparison words, sort and dedup
+use core::cmp::Ordering;
+
+use crate::sequence::Step;
+use crate::{Datatype, ProgramError};
+
+impl PartialEq for Datatype {
+    fn eq(&self, other : &Datatype) -> bool {
+        compare(self, other) == Ordering::Equal
+    }
+}
+
+//Total order over all values: numbers by value, booleans False before True, strings, lists and
+//quotations lexicographically, records by type and then fields. Values of different kinds are ordered by kind, see `kind`
+pub fn compare(a : &Datatype, b : &Datatype) -> Ordering {
+    match (a, b) {
+        (Datatype::Int(a), Datatype::Int(b)) => a.cmp(b),
+        (Datatype::Float(a), Datatype::Float(b)) => compare_floats(*a, *b),
+        (Datatype::Int(a), Datatype::Float(b)) => compare_int_float(*a, *b),
+        (Datatype::Float(a), Datatype::Int(b)) => compare_int_float(*b, *a).reverse(),
+        (Datatype::Rational(..) | Datatype::Int(_), Datatype::Rational(..) | Datatype::Int(_)) => compare_fractions(fraction(a), fraction(b)),
+        (Datatype::Rational(n, d), Datatype::Float(b)) => compare_fraction_float((*n, *d), *b),
+        (Datatype::Float(a), Datatype::Rational(n, d)) => compare_fraction_float((*n, *d), *a).reverse(),
+        (Datatype::Boolean(a), Datatype::Boolean(b)) => a.cmp(b),
+        (Datatype::String(a), Datatype::String(b)) => a.cmp(b),
+        (Datatype::List(a), Datatype::List(b)) => {
+            a.iter().zip(b)
+                .map(|(x, y)| compare(x, y))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(a.len().cmp(&b.len()))
+        },
+        (Datatype::Code(a, a_env), Datatype::Code(b, b_env)) => {
+            a.cmp(b).then_with(|| {
+                a_env.iter().zip(b_env)
+                    .map(|((x_name, x), (y_name, y))| x_name.cmp(y_name).then_with(|| compare(x, y)))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(a_env.len().cmp(&b_env.len()))
+            })
+        },
+        (Datatype::Symbol(a), Datatype::Symbol(b)) => a.cmp(b),
+        (Datatype::Sequence(a), Datatype::Sequence(b)) => {
+            compare(&a.seed, &b.seed)
+                .then_with(|| compare(&a.next, &b.next))
+                .then_with(|| compare_steps(&a.steps, &b.steps))
+        },
+        (Datatype::Record(a_tag, a_fields), Datatype::Record(b_tag, b_fields)) => {
+            a_tag.cmp(b_tag).then_with(|| {
+                a_fields.iter().zip(b_fields)
+                    .map(|((x_name, x), (y_name, y))| x_name.cmp(y_name).then_with(|| compare(x, y)))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(a_fields.len().cmp(&b_fields.len()))
+            })
+        },
+        _ => kind(a).cmp(&kind(b)),
+    }
+}
+
+//Sequences are equal when they are built the same way, their elements are never run to compare them
+fn compare_steps(a : &[Step], b : &[Step]) -> Ordering {
+    a.iter().zip(b)
+        .map(|(x, y)| {
+            let (x_kind, x_value) = step_key(x);
+            let (y_kind, y_value) = step_key(y);
+            x_kind.cmp(&y_kind).then_with(|| compare(&x_value, &y_value))
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(a.len().cmp(&b.len()))
+}
+
+fn step_key(step : &Step) -> (u8, Datatype) {
+    match step {
+        Step::Map(operation) => (0, operation.clone()),
+        Step::Filter(predicate) => (1, predicate.clone()),
+        Step::Take(count) => (2, Datatype::Int(*count as i128)),
+        Step::TakeWhile(predicate) => (3, predicate.clone()),
+    }
+}
+
+//0.0 and -0.0 are equal, NaN is equal to itself and larger than every other number
+fn compare_floats(a : f64, b : f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+    }
+}
+
+//Exact, rather than through `a as f64`: large ints next to each other would both round to the same float
+//and compare equal to it, and sorting needs equal to be transitive
+fn compare_int_float(a : i128, b : f64) -> Ordering {
+    if b.is_nan() || b >= i128::MAX as f64 {
+        return Ordering::Less;
+    }
+    if b < i128::MIN as f64 {
+        return Ordering::Greater;
+    }
+    let whole = libm::trunc(b);
+    a.cmp(&(whole as i128)).then_with(|| compare_floats(0.0, b - whole))
+}
+
+//Exact like `compare_int_float`, rounding n/d to a float would make it equal to floats that are not
+//equal to each other. The whole parts first, then the fractional parts one binary digit at a time, which
+//ends as the fraction of a float has finitely many digits
+fn compare_fraction_float((n, d) : (i128, i128), b : f64) -> Ordering {
+    if b.is_nan() || b >= i128::MAX as f64 {
+        return Ordering::Less;
+    }
+    if b < i128::MIN as f64 {
+        return Ordering::Greater;
+    }
+    let whole = libm::floor(b);
+    let ordering = n.div_euclid(d).cmp(&(whole as i128));
+    if ordering.is_ne() {
+        return ordering;
+    }
+
+    //Both fractions are below 1, and the remainder below the denominator so doubling it fits in a u128
+    let (mut r, d) = (n.rem_euclid(d) as u128, d as u128);
+    let mut f = b - whole;
+    while f != 0.0 {
+        r *= 2;
+        f *= 2.0;
+        let (x, y) = (r >= d, f >= 1.0);
+        if x != y {
+            return x.cmp(&y);
+        }
+        if x {
+            r -= d;
+            f -= 1.0;
+        }
+    }
+    (r != 0).cmp(&false)
+}
+
+fn fraction(value : &Datatype) -> (i128, i128) {
+    match value {
+        Datatype::Rational(n, d) => (*n, *d),
+        Datatype::Int(n) => (*n, 1),
+        _ => unreachable!(),
+    }
+}
+
+//Exact for all fractions with positive denominators: the whole parts first, then the fractional parts
+//by comparing their reciprocals the other way around, like the Euclidean algorithm
+fn compare_fractions((a, b) : (i128, i128), (c, d) : (i128, i128)) -> Ordering {
+    let (x, y) = (a.div_euclid(b), c.div_euclid(d));
+    let (r, s) = (a.rem_euclid(b), c.rem_euclid(d));
+    match x.cmp(&y).then(r.min(1).cmp(&s.min(1))) {
+        Ordering::Equal if r != 0 => compare_fractions((d, s), (b, r)),
+        ordering => ordering,
+    }
+}
+
+fn kind(value : &Datatype) -> u8 {
+    match value {
+        Datatype::Boolean(_) => 0,
+        Datatype::Int(_) | Datatype::Float(_) | Datatype::Rational(..) => 1,
+        Datatype::String(_) => 2,
+        Datatype::List(_) => 3,
+        Datatype::Code(..) => 4,
+        Datatype::Symbol(_) => 5,
+        Datatype::Sequence(_) => 6,
+        Datatype::Record(..) => 7,
+    }
+}
+
+//The ordering words `<`, `>`, `<=` and `>=`, which only compare values of the same kind
+pub fn ordered(a : Datatype, b : Datatype, accept : fn(Ordering) -> bool) -> Result<Datatype, ProgramError> {
+    if kind(&a) != kind(&b) {
+        return Err(ProgramError::InvalidOperation);
+    }
+    //b was below a on the stack, so `b a <` asks whether b is less than a
+    Ok(Datatype::Boolean(accept(compare(&b, &a))))
+}
@@ -0,0 +1,633 @@
+//Compiling a bprog program ahead of time to the source of a standalone Rust binary (`lab12 compile`). The
+//generated program runs on the library's values and builtins without reading any bprog at run time:
+//- literals become Rust values and builtins direct calls to the functions the interpreter uses, like
+//  `math::add` or `words::head`, with the stack words written out in Rust
+//- the quotations given to words like `map`, `if` and `case` become Rust functions, called for every element
+//- a function defined with a literal body, `name { ... } fun`, gets its body compiled too, and calls to it
+//  from anywhere, the interpreter included, run the compiled body
+//Everything else is run by the interpreter in the binary: quotations kept as values and run with `exec` or
+//as the steps of lazy sequences, the prelude, and the modules the program imports, which are embedded as
+//source. Compiled programs run without limits and tracing
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::case::pattern;
+pub use crate::case::Pattern;
+use crate::lexer::tokenize;
+use crate::modules::embedded_path;
+use crate::sequence::{Sequence, Step};
+use crate::{datatype, module_path, names_definition, nested, Compiled, Datatype, Env, Interpreter, ProgramError};
+
+//A quotation given to a word, compiled
+pub struct Quotation {
+    //The quotation as a value, for the words that keep it instead of running it, like `map` on a lazy sequence
+    pub value: fn() -> Datatype,
+    pub run: Compiled,
+}
+
+//Translates a program into the source of a Rust binary depending on this crate. The modules it imports are
+//read from the directory given, the one of the program file
+pub fn compile(source : &str, directory : Option<&Path>) -> Result<String, ProgramError> {
+    let mut compiler = Compiler { items: String::new(), count: 0 };
+    let body = compiler.body(source, &[])?;
+
+    let mut modules = String::new();
+    if let Some(directory) = directory {
+        for (path, source) in imported_modules(source, directory) {
+            writeln!(modules, "    vm.add_module({:?}, {:?});", path, source).unwrap();
+        }
+    }
+
+    Ok(format!(
+"//Compiled from bprog by `lab12 compile`
+#![allow(unused_imports)]
+use std::cmp::Ordering;
+
+use lab12::compile::{{Pattern, Quotation}};
+use lab12::sequence::{{Sequence, Step}};
+use lab12::{{compare, math, records, words, Datatype, Interpreter, ProgramError}};
+
+fn program(vm : &mut Interpreter) -> Result<(), ProgramError> {{
+{}    Ok(())
+}}
+{}
+fn main() {{
+    let mut vm = Interpreter::new();
+{}    match vm.run_compiled(program) {{
+        Ok(value) => println!(\"{{}}\", vm.format(value)),
+        Err(e) => {{
+            println!(\"Error: {{:?}}\", e);
+            std::process::exit(1);
+        }},
+    }}
+}}
+", body, compiler.items, modules))
+}
+
+struct Compiler {
+    //The functions and quotations compiled so far
+    items: String,
+    count: usize,
+}
+
+impl Compiler {
+    //The statements running the code. Scope is the local names bound around it, which hide builtins
+    fn body(&mut self, code : &str, scope : &[String]) -> Result<String, ProgramError> {
+        let mut tokens = tokenize(code);
+        tokens.reverse();
+        if tokens.last() != Some(&"|") {
+            return self.statements(&mut tokens, scope);
+        }
+
+        tokens.pop();
+        let mut names = Vec::new();
+        loop {
+            match tokens.pop() {
+                Some("|") => break,
+                Some(name) => names.push(name.to_string()),
+                None => return Err(ProgramError::IncompleteBinding),
+            }
+        }
+        let scope : Vec<String> = scope.iter().chain(&names).cloned().collect();
+        let statements = indent(&self.statements(&mut tokens, &scope)?);
+        let names : Vec<String> = names.iter().map(|name| format!("{:?}", name)).collect();
+        Ok(format!("    vm.with_names(&[{}], |vm| {{\n{}        Ok(())\n    }})?;\n", names.join(", "), statements))
+    }
+
+    fn statements(&mut self, tokens : &mut Vec<&str>, scope : &[String]) -> Result<String, ProgramError> {
+        let mut statements = String::new();
+        while let Some(token) = tokens.pop() {
+            let statement = match datatype(token, tokens)? {
+                //A function with a literal body is compiled with it
+                Some(Datatype::Code(body, env)) if tokens.last() == Some(&"fun") => {
+                    tokens.pop();
+                    let scope : Vec<String> = scope.iter().chain(env.iter().map(|(name, _)| name)).cloned().collect();
+                    let function = self.function(&body, &scope)?;
+                    format!("vm.define_compiled({}, {})?;", rust_value(&Datatype::Code(body, env)), function)
+                },
+                Some(value) => format!("vm.push_value({});", rust_value(&value)),
+                None if scope.iter().any(|name| name == token) => format!("vm.call_word({:?})?;", token),
+                None => self.word(token, tokens, scope)?,
+            };
+            writeln!(statements, "    {}", statement).unwrap();
+        }
+        Ok(statements)
+    }
+
+    //A builtin as a direct call, reading the quotations it takes. Other words are local names or functions
+    //looked up when they run
+    fn word(&mut self, word : &str, tokens : &mut Vec<&str>, scope : &[String]) -> Result<String, ProgramError> {
+        if let Some(function) = unary(word) {
+            return Ok(format!("vm.unary({})?;", function));
+        }
+        if let Some(function) = binary(word) {
+            return Ok(format!("vm.binary({})?;", function));
+        }
+        let statement = match word {
+            "+" | "-" | "*" | "/" | "==" | "!=" => format!("vm.operator({:?}, {})?;", word, operator(word)),
+            "max" => "vm.method(Interpreter::max)?;".to_string(),
+            "min" => "vm.method(Interpreter::min)?;".to_string(),
+            "sort" => "vm.method(Interpreter::sort)?;".to_string(),
+            "dedup" => "vm.method(Interpreter::dedup)?;".to_string(),
+            "toList" => "vm.method(Interpreter::to_list)?;".to_string(),
+            "swap" => "{ let a = vm.pop()?; let b = vm.pop()?; vm.stack.push(a); vm.stack.push(b); }".to_string(),
+            "pop" => "vm.pop()?;".to_string(),
+            "dup" => "{ let a = vm.pop()?; vm.stack.push(a.clone()); vm.stack.push(a); }".to_string(),
+            "over" => "{ let a = vm.pop()?; let b = vm.pop()?; vm.stack.push(b.clone()); vm.stack.push(a); vm.stack.push(b); }".to_string(),
+            "rot" => "{ let c = vm.pop()?; let b = vm.pop()?; let a = vm.pop()?; vm.stack.push(b); vm.stack.push(c); vm.stack.push(a); }".to_string(),
+            "nip" => "{ let a = vm.pop()?; vm.pop()?; vm.stack.push(a); }".to_string(),
+            "tuck" => "{ let b = vm.pop()?; let a = vm.pop()?; vm.stack.push(b.clone()); vm.stack.push(a); vm.stack.push(b); }".to_string(),
+            "2dup" => "{ let b = vm.pop()?; let a = vm.pop()?; vm.stack.push(a.clone()); vm.stack.push(b.clone()); vm.stack.push(a); vm.stack.push(b); }".to_string(),
+            "depth" => "vm.stack.push(Datatype::Int(vm.stack.len() as i128));".to_string(),
+            "clear" => "vm.stack.clear();".to_string(),
+            "words" => "{ let words = vm.words(); vm.stack.push(words); }".to_string(),
+            "precision" => "{ let digits = vm.pop()?; vm.set_precision(digits)?; }".to_string(),
+            "exec" => "{ let code = vm.pop()?; vm.exec(code)?; }".to_string(),
+            "fun" => "{ let body = vm.pop()?; let name = vm.pop()?; vm.define(name, body)?; }".to_string(),
+            "struct" => "{ let fields = vm.pop()?; let name = vm.pop()?; vm.define_struct(name, fields)?; }".to_string(),
+            "record" => "{ let fields = vm.pop()?; let tag = vm.pop()?; let record = vm.record(tag, fields)?; vm.stack.push(record); }".to_string(),
+            "help" => {
+                let word = tokens.pop().ok_or(ProgramError::UnknownWord)?;
+                let word = word.strip_prefix('\'').filter(|word| !word.is_empty()).unwrap_or(word);
+                format!("{{ let text = vm.help({:?})?; vm.stack.push(text); }}", word)
+            },
+            "import" => format!("vm.import({:?})?;", module_path(tokens)?),
+            "if" => {
+                let then = self.quotation(tokens, scope)?;
+                let otherwise = self.quotation(tokens, scope)?;
+                format!("vm.run_if(&{}, &{})?;", then, otherwise)
+            },
+            "map" | "filter" | "takeWhile" | "each" | "foldl" | "times" | "iterate" | "pmap" | "pfilter" => {
+                let quotation = self.quotation(tokens, scope)?;
+                format!("vm.{}(&{})?;", runner(word), quotation)
+            },
+            "case" => self.case(tokens, scope)?,
+            _ if names_definition(tokens) => format!("vm.name_word({:?})?;", word),
+            _ => format!("vm.call_word({:?})?;", word),
+        };
+        Ok(statement)
+    }
+
+    //The quotation after a word, as the name of a constant holding it. The interpreter reads it the same way
+    fn quotation(&mut self, tokens : &mut Vec<&str>, scope : &[String]) -> Result<String, ProgramError> {
+        let token = tokens.pop().ok_or(ProgramError::ExpectedQuotation)?;
+        let (value, run) = match nested(token, tokens, 0)? {
+            Some(Datatype::Code(body, env)) if token == "{" => {
+                let function = self.function(&body, scope)?;
+                (Datatype::Code(body, env), function)
+            },
+            //Any other value is run like `exec` runs it, a quoted word is called and the rest is pushed
+            Some(value) => (value.clone(), format!("|vm| vm.run_value({})", rust_value(&value))),
+            //A single word is a quotation of its own
+            None => (Datatype::Code(token.to_string(), Vec::new()), self.function(token, scope)?),
+        };
+
+        self.count += 1;
+        let name = format!("QUOTATION_{}", self.count);
+        writeln!(self.items, "\nconst {} : Quotation = Quotation {{ value: || {}, run: {} }};", name, rust_value(&value), run).unwrap();
+        Ok(name)
+    }
+
+    //A Rust function running the code, given by its name
+    fn function(&mut self, code : &str, scope : &[String]) -> Result<String, ProgramError> {
+        let body = self.body(code, scope)?;
+        self.count += 1;
+        let name = format!("function_{}", self.count);
+        write!(self.items, "\nfn {}(vm : &mut Interpreter) -> Result<(), ProgramError> {{\n{}    Ok(())\n}}\n", name, body).unwrap();
+        Ok(name)
+    }
+
+    //The patterns of `case` with their branches, the names bound by `[ head | tail ]` in scope of its branch
+    fn case(&mut self, tokens : &mut Vec<&str>, scope : &[String]) -> Result<String, ProgramError> {
+        let token = tokens.pop().ok_or(ProgramError::ExpectedQuotation)?;
+        let branches = match nested(token, tokens, 0)? {
+            Some(Datatype::Code(branches, _)) => branches,
+            Some(_) => return Err(ProgramError::ExpectedQuotation),
+            None => token.to_string(),
+        };
+        let mut branches = tokenize(&branches);
+        branches.reverse();
+
+        let mut compiled = Vec::new();
+        while !branches.is_empty() {
+            let (pattern, bound) = match pattern(&mut branches)? {
+                Pattern::Literal(value) => (format!("Pattern::Literal({})", rust_value(&value)), Vec::new()),
+                Pattern::Type(name) => (format!("Pattern::Type({:?}.to_string())", name), Vec::new()),
+                Pattern::HeadTail(head, tail) => (format!("Pattern::HeadTail({:?}.to_string(), {:?}.to_string())", head, tail), vec![head, tail]),
+                Pattern::Default => ("Pattern::Default".to_string(), Vec::new()),
+            };
+            let scope : Vec<String> = scope.iter().chain(&bound).cloned().collect();
+            let quotation = self.quotation(&mut branches, &scope)?;
+            compiled.push(format!("({}, &{})", pattern, quotation));
+        }
+        Ok(format!("vm.run_case(vec![{}])?;", compiled.join(", ")))
+    }
+}
+
+fn indent(statements : &str) -> String {
+    statements.lines().map(|line| format!("    {}\n", line)).collect()
+}
+
+fn unary(word : &str) -> Option<&'static str> {
+    let function = match word {
+        "abs" => "math::abs",
+        "floor" => "math::floor",
+        "ceil" => "math::ceil",
+        "round" => "math::round",
+        "sqrt" => "math::sqrt",
+        "toInt" => "math::to_int",
+        "toFloat" => "math::to_float",
+        "numerator" => "math::numerator",
+        "denominator" => "math::denominator",
+        "not" => "words::not",
+        "length" => "words::length",
+        "splitWords" => "words::split_words",
+        "typeOf" => "words::type_of",
+        "parseInteger" => "words::parse_integer",
+        "parseFloat" => "words::parse_float",
+        "empty" => "words::empty",
+        "head" => "words::head",
+        "tail" => "words::tail",
+        _ => return None,
+    };
+    Some(function)
+}
+
+fn binary(word : &str) -> Option<&'static str> {
+    let function = match word {
+        "div" => "math::div",
+        "pow" => "math::pow",
+        "&&" => "words::and",
+        "||" => "words::or",
+        "take" => "words::take",
+        "cons" => "words::cons",
+        "append" => "words::append",
+        "field" => "records::field",
+        "<" => "|a, b| compare::ordered(a, b, Ordering::is_lt)",
+        ">" => "|a, b| compare::ordered(a, b, Ordering::is_gt)",
+        "<=" => "|a, b| compare::ordered(a, b, Ordering::is_le)",
+        ">=" => "|a, b| compare::ordered(a, b, Ordering::is_ge)",
+        _ => return None,
+    };
+    Some(function)
+}
+
+//The builtin behind an operator that record types can overload
+fn operator(word : &str) -> &'static str {
+    match word {
+        "+" => "math::add",
+        "-" => "math::subtract",
+        "*" => "math::multiply",
+        "/" => "math::divide",
+        "==" => "words::equal",
+        _ => "words::not_equal",
+    }
+}
+
+//The method running a word on a compiled quotation
+fn runner(word : &str) -> &'static str {
+    match word {
+        "map" => "run_map",
+        "filter" => "run_filter",
+        "takeWhile" => "run_take_while",
+        "each" => "run_each",
+        "foldl" => "run_foldl",
+        "times" => "run_times",
+        "iterate" => "run_iterate",
+        "pmap" => "run_pmap",
+        _ => "run_pfilter",
+    }
+}
+
+//The modules a program imports, itself or through other modules, with their paths relative to the
+//program's directory. Only those found next to the file importing them are embedded, any other is looked
+//for at run time like the interpreter does
+fn imported_modules(source : &str, directory : &Path) -> Vec<(String, String)> {
+    let mut modules : Vec<(String, String)> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut pending = vec![(PathBuf::new(), source.to_string())];
+    while let Some((file, source)) = pending.pop() {
+        let base = file.parent().map(Path::to_path_buf).unwrap_or_default();
+        for import in imports(&source) {
+            let module = match embedded_path(&base, Path::new(&import)) {
+                Some(module) => module,
+                None => continue,
+            };
+            let name : Vec<String> = module.components().map(|component| component.as_os_str().to_string_lossy().into_owned()).collect();
+            let name = name.join("/");
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            if let Ok(source) = fs::read_to_string(directory.join(&module)) {
+                modules.push((name, source.clone()));
+                pending.push((module, source));
+            }
+        }
+    }
+    modules
+}
+
+//The paths after every `import` in the source, including those inside quotations
+fn imports(source : &str) -> Vec<String> {
+    let mut tokens = tokenize(source);
+    tokens.reverse();
+    let mut paths = Vec::new();
+    while let Some(token) = tokens.pop() {
+        if token == "import" {
+            paths.extend(module_path(&mut tokens).ok());
+        }
+    }
+    paths
+}
+
+//A Rust expression building the value
+fn rust_value(value : &Datatype) -> String {
+    match value {
+        Datatype::Int(value) => format!("Datatype::Int({}i128)", value),
+        Datatype::Float(value) if value.is_nan() => "Datatype::Float(f64::NAN)".to_string(),
+        Datatype::Float(value) if value.is_infinite() && *value > 0.0 => "Datatype::Float(f64::INFINITY)".to_string(),
+        Datatype::Float(value) if value.is_infinite() => "Datatype::Float(f64::NEG_INFINITY)".to_string(),
+        Datatype::Float(value) => format!("Datatype::Float({:?}f64)", value),
+        Datatype::Rational(n, d) => format!("Datatype::Rational({}i128, {}i128)", n, d),
+        Datatype::Boolean(value) => format!("Datatype::Boolean({})", value),
+        Datatype::String(value) => format!("Datatype::String({:?}.to_string())", value),
+        Datatype::List(items) => format!("Datatype::List(vec![{}])", items.iter().map(rust_value).collect::<Vec<_>>().join(", ")),
+        Datatype::Code(code, env) => format!("Datatype::Code({:?}.to_string(), {})", code, rust_env(env)),
+        Datatype::Symbol(name) => format!("Datatype::Symbol({:?}.to_string())", name),
+        Datatype::Sequence(sequence) => {
+            let steps : Vec<String> = sequence.steps.iter().map(|step| match step {
+                Step::Map(operation) => format!("Step::Map({})", rust_value(operation)),
+                Step::Filter(predicate) => format!("Step::Filter({})", rust_value(predicate)),
+                Step::Take(count) => format!("Step::Take({})", count),
+                Step::TakeWhile(predicate) => format!("Step::TakeWhile({})", rust_value(predicate)),
+            }).collect();
+            format!("Datatype::Sequence(Sequence {{ seed: Box::new({}), next: Box::new({}), steps: vec![{}] }})",
+                rust_value(&sequence.seed), rust_value(&sequence.next), steps.join(", "))
+        },
+        Datatype::Record(tag, fields) => format!("Datatype::Record({:?}.to_string(), {})", tag, rust_env(fields)),
+    }
+}
+
+fn rust_env(env : &Env) -> String {
+    let values : Vec<String> = env.iter().map(|(name, value)| format!("({:?}.to_string(), {})", name, rust_value(value))).collect();
+    format!("vec![{}]", values.join(", "))
+}
+
+//The entry points the generated code calls. They do what the words do in the interpreter, with the
+//quotations run as Rust functions. A compiled quotation runs with the local names of the code around it,
+//which are the ones the interpreter would have captured for it
+impl Interpreter {
+    //Runs a compiled program like `run` does a source one
+    pub fn run_compiled(&mut self, program : Compiled) -> Result<Datatype, ProgramError> {
+        self.stack.clear();
+        self.steps = 0;
+        self.depth += 1;
+        let result = program(self);
+        self.depth -= 1;
+        result?;
+
+        match self.stack.len() {
+            0 => Err(ProgramError::StackEmpty),
+            1 => self.pop(),
+            _ => Err(ProgramError::ProgramFinishedWithMultipleValues),
+        }
+    }
+
+    pub fn push_value(&mut self, value : Datatype) {
+        let value = self.capture(value);
+        self.stack.push(value);
+    }
+
+    //A word that is not a builtin: a local name or a function
+    pub fn call_word(&mut self, word : &str) -> Result<(), ProgramError> {
+        if let Some(value) = self.local(word) {
+            self.stack.push(value);
+            return Ok(());
+        }
+        let name = self.resolve(word).ok_or(ProgramError::UnknownWord)?;
+        self.call(&name)
+    }
+
+    //The word before the body of a `fun` or the fields of a `struct`, which is the name itself where it is not
+    //defined yet
+    pub fn name_word(&mut self, word : &str) -> Result<(), ProgramError> {
+        if self.local(word).is_some() || self.resolve(word).is_some() {
+            return self.call_word(word);
+        }
+        self.stack.push(Datatype::Symbol(word.to_string()));
+        Ok(())
+    }
+
+    //Runs code starting with `| a b |`, the names getting the values popped for them
+    pub fn with_names(&mut self, names : &[&str], body : impl FnOnce(&mut Interpreter) -> Result<(), ProgramError>) -> Result<(), ProgramError> {
+        let frame = self.pop_names(names.iter().map(|name| name.to_string()).collect())?;
+        self.locals.push(frame);
+        let result = body(self);
+        self.locals.pop();
+        result
+    }
+
+    //Runs a value given in place of a quotation, the way `exec` does
+    pub fn run_value(&mut self, value : Datatype) -> Result<(), ProgramError> {
+        let value = self.capture(value);
+        self.run_quotation(&value)
+    }
+
+    pub fn unary(&mut self, builtin : fn(Datatype) -> Result<Datatype, ProgramError>) -> Result<(), ProgramError> {
+        let a = self.pop()?;
+        self.stack.push(builtin(a)?);
+        Ok(())
+    }
+
+    pub fn binary(&mut self, builtin : fn(Datatype, Datatype) -> Result<Datatype, ProgramError>) -> Result<(), ProgramError> {
+        let a = self.pop()?;
+        let b = self.pop()?;
+        self.stack.push(builtin(a, b)?);
+        Ok(())
+    }
+
+    //A builtin operator, or the overload of it when one of the operands is a record whose type has one
+    pub fn operator(&mut self, operator : &str, builtin : fn(Datatype, Datatype) -> Result<Datatype, ProgramError>) -> Result<(), ProgramError> {
+        if self.run_overload(operator)? {
+            return Ok(());
+        }
+        self.binary(builtin)
+    }
+
+    pub fn method(&mut self, builtin : fn(&mut Interpreter, Datatype) -> Result<Datatype, ProgramError>) -> Result<(), ProgramError> {
+        let a = self.pop()?;
+        let value = builtin(self, a)?;
+        self.stack.push(value);
+        Ok(())
+    }
+
+    //`name { ... } fun` with the body compiled to the given function
+    pub fn define_compiled(&mut self, body : Datatype, native : Compiled) -> Result<(), ProgramError> {
+        let body = self.capture(body);
+        let name = self.pop()?;
+        self.define_function(name, body, Some(native))
+    }
+
+    pub fn run_if(&mut self, then : &Quotation, otherwise : &Quotation) -> Result<(), ProgramError> {
+        match self.pop()? {
+            Datatype::Boolean(true) => (then.run)(self),
+            Datatype::Boolean(false) => (otherwise.run)(self),
+            _ => Err(ProgramError::ExpectedBool),
+        }
+    }
+
+    pub fn run_map(&mut self, operation : &Quotation) -> Result<(), ProgramError> {
+        let value = match self.pop()? {
+            Datatype::List(list) => {
+                let mut new_list = Vec::new();
+                for item in list {
+                    new_list.push(self.apply_compiled(operation, item)?);
+                }
+                Datatype::List(new_list)
+            },
+            Datatype::Sequence(sequence) => sequence.then(Step::Map(self.capture((operation.value)()))),
+            _ => return Err(ProgramError::ExpectedList),
+        };
+        self.stack.push(value);
+        Ok(())
+    }
+
+    pub fn run_filter(&mut self, predicate : &Quotation) -> Result<(), ProgramError> {
+        let value = match self.pop()? {
+            Datatype::List(list) => {
+                let mut new_list = Vec::new();
+                for item in list {
+                    if self.test_compiled(predicate, item.clone())? {
+                        new_list.push(item);
+                    }
+                }
+                Datatype::List(new_list)
+            },
+            Datatype::Sequence(sequence) => sequence.then(Step::Filter(self.capture((predicate.value)()))),
+            _ => return Err(ProgramError::ExpectedList),
+        };
+        self.stack.push(value);
+        Ok(())
+    }
+
+    pub fn run_take_while(&mut self, predicate : &Quotation) -> Result<(), ProgramError> {
+        let value = match self.pop()? {
+            Datatype::List(list) => {
+                let mut new_list = Vec::new();
+                for item in list {
+                    if !self.test_compiled(predicate, item.clone())? {
+                        break;
+                    }
+                    new_list.push(item);
+                }
+                Datatype::List(new_list)
+            },
+            Datatype::Sequence(sequence) => sequence.then(Step::TakeWhile(self.capture((predicate.value)()))),
+            _ => return Err(ProgramError::ExpectedList),
+        };
+        self.stack.push(value);
+        Ok(())
+    }
+
+    pub fn run_each(&mut self, operation : &Quotation) -> Result<(), ProgramError> {
+        match self.pop()? {
+            Datatype::List(list) => {
+                for item in list {
+                    self.stack.push(item);
+                    (operation.run)(self)?;
+                }
+                Ok(())
+            },
+            _ => Err(ProgramError::InvalidOperation),
+        }
+    }
+
+    pub fn run_foldl(&mut self, operation : &Quotation) -> Result<(), ProgramError> {
+        let mut accumulator = self.pop()?;
+        let list = match self.pop()? {
+            Datatype::List(list) => list,
+            _ => return Err(ProgramError::ExpectedList),
+        };
+        for item in list {
+            self.stack.push(accumulator);
+            self.stack.push(item);
+            (operation.run)(self)?;
+            accumulator = self.pop()?;
+        }
+        self.stack.push(accumulator);
+        Ok(())
+    }
+
+    pub fn run_times(&mut self, operation : &Quotation) -> Result<(), ProgramError> {
+        match self.pop()? {
+            Datatype::Int(count) => {
+                for _ in 0..count {
+                    (operation.run)(self)?;
+                }
+                Ok(())
+            },
+            _ => Err(ProgramError::InvalidOperation),
+        }
+    }
+
+    //The sequence keeps the quotation as a value, its elements are computed by the interpreter
+    pub fn run_iterate(&mut self, next : &Quotation) -> Result<(), ProgramError> {
+        let seed = self.pop()?;
+        let next = self.capture((next.value)());
+        self.stack.push(Datatype::Sequence(Sequence::new(seed, next)));
+        Ok(())
+    }
+
+    pub fn run_pmap(&mut self, operation : &Quotation) -> Result<(), ProgramError> {
+        let list = self.pop()?;
+        let items = self.items(list)?;
+        let results = self.parallel(&items, |worker, item| worker.apply_compiled(operation, item))?;
+        self.stack.push(Datatype::List(results));
+        Ok(())
+    }
+
+    pub fn run_pfilter(&mut self, predicate : &Quotation) -> Result<(), ProgramError> {
+        let list = self.pop()?;
+        let items = self.items(list)?;
+        let keep = self.parallel(&items, |worker, item| worker.test_compiled(predicate, item))?;
+        self.stack.push(Datatype::List(items.into_iter().zip(keep).filter(|(_, keep)| *keep).map(|(item, _)| item).collect()));
+        Ok(())
+    }
+
+    //Runs the branch of the first matching pattern, with the names the pattern binds
+    pub fn run_case(&mut self, branches : Vec<(Pattern, &Quotation)>) -> Result<(), ProgramError> {
+        let mut value = self.pop()?;
+        for (pattern, branch) in branches {
+            let bound = match self.matching(pattern, value)? {
+                Ok(bound) => bound,
+                Err(unmatched) => {
+                    value = unmatched;
+                    continue;
+                },
+            };
+            if bound.is_empty() {
+                return (branch.run)(self);
+            }
+            self.locals.push(bound);
+            let result = (branch.run)(self);
+            self.locals.pop();
+            return result;
+        }
+        Err(ProgramError::NoMatch)
+    }
+
+    fn apply_compiled(&mut self, operation : &Quotation, item : Datatype) -> Result<Datatype, ProgramError> {
+        self.stack.push(item);
+        (operation.run)(self)?;
+        self.pop()
+    }
+
+    fn test_compiled(&mut self, predicate : &Quotation, item : Datatype) -> Result<bool, ProgramError> {
+        match self.apply_compiled(predicate, item)? {
+            Datatype::Boolean(value) => Ok(value),
+            _ => Err(ProgramError::ExpectedBool),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//Canonical printing of values and formatting of bprog source files (`lab12 fmt`)
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::lexer::{lex, tokenize, unbalanced};
+use crate::sequence::{Sequence, Step};
+use crate::{Datatype, Env, ProgramError};
+
+const INDENT : &str = "    ";
+
+//Prints a value the way it is written in bprog, the output parses back to an equal value
+pub fn format_stack_item(stack_item : Datatype) -> String {
+    format_value(stack_item, None)
+}
+
+//Like `format_stack_item`, with floats rounded to the given number of decimals. At least one decimal is
+//printed, so that the output still reads back as a float, though a rounded one is not the same value
+pub fn format_value(stack_item : Datatype, precision : Option<usize>) -> String {
+    format_styled(stack_item, &Style { precision, show: &|_| None })
+}
+
+//How values are printed: the float precision, and the text of records whose type defines `show`
+pub(crate) struct Style<'a> {
+    pub precision: Option<usize>,
+    pub show: &'a dyn Fn(&Datatype) -> Option<String>,
+}
+
+pub(crate) fn format_styled(stack_item : Datatype, style : &Style) -> String {
+    if let Some(text) = (style.show)(&stack_item) {
+        return text;
+    }
+    match stack_item {
+        Datatype::Int(value) => value.to_string(),
+        Datatype::Float(value) => format_float(value, style.precision),
+        Datatype::Rational(n, d) => format!("{}/{}", n, d),
+        Datatype::Boolean(value) => (if value { "True" } else { "False" }).to_string(),
+        Datatype::List(list) => format_sequence("[", list, "]", style),
+        Datatype::String(value) => format!("\" {} \"", escape(&value)),
+        Datatype::Code(code, env) if !env.is_empty() => format_closure(code, env, style),
+        Datatype::Code(code, _) if code.is_empty() => "{ }".to_string(),
+        Datatype::Code(code, _) => format!("{{ {} }}", code),
+        Datatype::Symbol(name) => format!("'{}", name),
+        Datatype::Sequence(sequence) => format_lazy(sequence, style),
+        Datatype::Record(tag, fields) => format_record(tag, fields, style),
+    }
+}
+
+//A closure prints as the binding that creates it in parentheses, e.g. `( 5 { | n | { n + } } exec )`, which
+//reads back as the closure itself, inside a list as well
+fn format_closure(code : String, env : Env, style : &Style) -> String {
+    let mut text = String::new();
+    let mut names = Vec::new();
+    for (name, value) in env {
+        text.push_str(&format_styled(value, style));
+        text.push(' ');
+        names.push(name);
+    }
+    format!("( {}{{ | {} | {} }} exec )", text, names.join(" "), format_styled(Datatype::Code(code, Vec::new()), style))
+}
+
+//A record prints as the words that build it without its constructor in parentheses, e.g.
+//`( 1 2 'point [ x y ] record )`, so that it reads back where the struct is not defined
+fn format_record(tag : String, fields : Vec<(String, Datatype)>, style : &Style) -> String {
+    let mut text = String::from("(");
+    let mut names = String::from("[");
+    for (name, value) in fields {
+        text.push(' ');
+        text.push_str(&format_styled(value, style));
+        names.push(' ');
+        names.push_str(&name);
+    }
+    format!("{} '{} {} ] record )", text, tag, names)
+}
+
+//A lazy sequence prints as the words that build it in parentheses, e.g. `( 1 iterate { 2 * } 5 take )`,
+//so that it reads back as one value inside a list as well
+fn format_lazy(sequence : Sequence, style : &Style) -> String {
+    let mut text = format!("( {} iterate {}", format_styled(*sequence.seed, style), format_styled(*sequence.next, style));
+    for step in sequence.steps {
+        let step = match step {
+            Step::Map(operation) => format!("map {}", format_styled(operation, style)),
+            Step::Filter(predicate) => format!("filter {}", format_styled(predicate, style)),
+            Step::Take(count) => format!("{} take", count),
+            Step::TakeWhile(predicate) => format!("takeWhile {}", format_styled(predicate, style)),
+        };
+        text.push(' ');
+        text.push_str(&step);
+    }
+    text.push_str(" )");
+    text
+}
+
+fn format_sequence(open : &str, items : Vec<Datatype>, close : &str, style : &Style) -> String {
+    let mut text = open.to_string();
+    for item in items {
+        text.push(' ');
+        text.push_str(&format_styled(item, style));
+    }
+    text.push(' ');
+    text.push_str(close);
+    text
+}
+
+//The string as it is written between the quotes of a literal
+fn escape(value : &str) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+//Floats are told apart from integers by the decimal point, which {:?} leaves out in exponent notation (1e-7)
+fn format_float(value : f64, precision : Option<usize>) -> String {
+    if let (Some(digits), true) = (precision, value.is_finite()) {
+        return format!("{:.*}", digits.max(1), value);
+    }
+    let text = format!("{:?}", value);
+    if text.contains('.') || !value.is_finite() {
+        text
+    } else {
+        text.replacen('e', ".0e", 1)
+    }
+}
+
+//Reformats source code: single spaces between tokens, string literals as they are, the line breaks of the original
+//and four spaces of indentation for every quotation, list or value in parentheses left open at the start of a line
+pub fn format_source(source : &str) -> Result<String, ProgramError> {
+    if let Some((_, error)) = unbalanced(&lex(source)).into_iter().next() {
+        return Err(error);
+    }
+
+    let mut formatted = String::new();
+    let mut depth : usize = 0;
+    let mut blank_line = false;
+
+    for line in source.lines() {
+        let tokens : Vec<&str> = tokenize(line);
+        if tokens.is_empty() {
+            //Runs of blank lines are kept as a single one, and only between code
+            blank_line = !formatted.is_empty();
+            continue;
+        }
+
+        //Lines starting with closing brackets line up with the line that opened them
+        let mut indent = depth;
+        let mut at_line_start = true;
+        for token in &tokens {
+            match *token {
+                "{" | "[" | "(" => depth += 1,
+                "}" | "]" | ")" => depth -= 1,
+                _ => (),
+            }
+            if at_line_start && matches!(*token, "}" | "]" | ")") {
+                indent -= 1;
+            } else {
+                at_line_start = false;
+            }
+        }
+
+        if blank_line {
+            formatted.push('\n');
+            blank_line = false;
+        }
+        formatted.push_str(&INDENT.repeat(indent));
+        formatted.push_str(&tokens.join(" "));
+        formatted.push('\n');
+    }
+    Ok(formatted)
+}
@@ -0,0 +1,162 @@
+//Splitting source code into tokens with their positions, shared by the interpreter, the formatter and the language server
+use alloc::vec::Vec;
+
+use crate::ProgramError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    //Zero-based line and column, the column counted in UTF-16 code units like LSP does
+    pub line: usize,
+    pub column: usize,
+}
+
+impl<'a> Token<'a> {
+    pub fn end_column(&self) -> usize {
+        self.column + self.text.encode_utf16().count()
+    }
+
+    //The word of a quoted word like 'inc, other tokens are returned as they are
+    pub fn unquoted(self) -> Token<'a> {
+        match self.text.strip_prefix('\'') {
+            Some(word) if !word.is_empty() => Token { text: word, line: self.line, column: self.column + 1 },
+            _ => self,
+        }
+    }
+}
+
+pub fn tokenize(source : &str) -> Vec<&str> {
+    lex(source).into_iter().map(|token| token.text).collect()
+}
+
+//Tokens are separated by whitespace, except for string literals: a token starting with `"` runs up to the
+//next `"` that is not escaped with a backslash, spaces included, or to the end of the line when there is none
+pub fn lex(source : &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for (line_number, line) in source.lines().enumerate() {
+        let mut column = 0;
+        let mut start : Option<(usize, usize)> = None;
+        let mut chars = line.char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            match (c.is_whitespace(), start) {
+                (false, None) if c == '"' => {
+                    let mut end = line.len();
+                    let mut string_column = column + 1;
+                    while let Some((j, c)) = chars.next() {
+                        string_column += c.len_utf16();
+                        match c {
+                            '\\' => {
+                                if let Some((_, escaped)) = chars.next() {
+                                    string_column += escaped.len_utf16();
+                                }
+                            },
+                            '"' => {
+                                end = j + 1;
+                                break;
+                            },
+                            _ => (),
+                        }
+                    }
+                    tokens.push(Token { text: &line[i..end], line: line_number, column });
+                    column = string_column;
+                    continue;
+                },
+                (false, None) => start = Some((i, column)),
+                (true, Some((start_byte, start_column))) => {
+                    tokens.push(Token { text: &line[start_byte..i], line: line_number, column: start_column });
+                    start = None;
+                },
+                _ => (),
+            }
+            column += c.len_utf16();
+        }
+        if let Some((start_byte, start_column)) = start {
+            tokens.push(Token { text: &line[start_byte..], line: line_number, column: start_column });
+        }
+    }
+    tokens
+}
+
+fn opening_error(bracket : &str) -> ProgramError {
+    match bracket {
+        "{" => ProgramError::IncompleteQuotation,
+        "(" => ProgramError::IncompleteValue,
+        _ => ProgramError::IncompleteList,
+    }
+}
+
+//Unclosed or unmatched brackets and strings, each with the token where the problem is
+pub fn unbalanced<'a>(tokens : &[Token<'a>]) -> Vec<(Token<'a>, ProgramError)> {
+    let mut problems = Vec::new();
+    let mut open : Vec<Token> = Vec::new();
+
+    for token in tokens {
+        match token.text {
+            text if text.starts_with('"') && !is_closed_string(text) => problems.push((*token, ProgramError::IncompleteString)),
+            "{" | "[" | "(" => open.push(*token),
+            "}" | "]" | ")" => {
+                let opening = match token.text {
+                    "}" => "{",
+                    ")" => "(",
+                    _ => "[",
+                };
+                match open.last() {
+                    Some(last) if last.text == opening => {
+                        open.pop();
+                    },
+                    _ => problems.push((*token, opening_error(opening))),
+                }
+            },
+            _ => (),
+        }
+    }
+
+    for bracket in open {
+        problems.push((bracket, opening_error(bracket.text)));
+    }
+    problems
+}
+
+//The names of functions defined with `name { ... } fun`
+pub fn definitions<'a>(tokens : &[Token<'a>]) -> Vec<Token<'a>> {
+    let mut found = Vec::new();
+    let mut open : Vec<usize> = Vec::new();
+    //Start and end index of the last quotation that was closed
+    let mut last_quotation : Option<(usize, usize)> = None;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token.text {
+            "{" => open.push(i),
+            "}" => last_quotation = open.pop().map(|start| (start, i)),
+            "fun" => {
+                if let Some((start, end)) = last_quotation {
+                    if end + 1 == i && start > 0 && is_name(tokens[start - 1].text) {
+                        found.push(tokens[start - 1].unquoted());
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+    found
+}
+
+fn is_name(text : &str) -> bool {
+    !matches!(text, "{" | "}" | "[" | "]" | "(" | ")") && !text.starts_with('"')
+}
+
+//Whether a string token ends with a closing `"` of its own, rather than running to the end of the line
+pub fn is_closed_string(text : &str) -> bool {
+    let mut chars = text.chars().skip(1);
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            },
+            '"' => return true,
+            _ => (),
+        }
+    }
+    false
+}
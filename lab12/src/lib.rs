@@ -0,0 +1,1362 @@
+//The evaluator core only needs `alloc`, so that it can be embedded in hosts without std. File
+//imports, tracing and the language server come with the `std` feature, which is on by default
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+
+pub mod builtins;
+mod case;
+pub mod compare;
+#[cfg(feature = "std")]
+pub mod compile;
+pub mod format;
+pub mod lexer;
+pub mod math;
+#[cfg(feature = "std")]
+pub mod lsp;
+mod modules;
+mod parallel;
+pub mod records;
+pub mod sequence;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "std")]
+mod snapshot;
+#[cfg(feature = "std")]
+pub mod trace;
+pub mod words;
+
+pub use format::{format_source, format_stack_item, format_value};
+use format::{format_styled, Style};
+pub use modules::PRELUDE;
+#[cfg(feature = "std")]
+pub use snapshot::SNAPSHOT_VERSION;
+use builtins::{builtin, BUILTINS};
+use compare::{compare, ordered};
+use lexer::{is_closed_string, tokenize};
+use records::field;
+use words::{and, append, cons, empty, equal, head, length, not, not_equal, or, parse_float, parse_integer, split_words, tail, take, type_of};
+use math::{abs, add, ceil, denominator, div, divide, floor, max, min, multiply, numerator, pow, rational, round, sqrt, subtract, to_float, to_int};
+use sequence::{Sequence, Step};
+#[cfg(feature = "std")]
+use trace::Tracer;
+
+#[derive(Debug)]
+pub enum ProgramError {
+    InvalidOperation,
+    IncompleteList,
+    IncompleteString,
+    IncompleteQuotation,
+    StackEmpty,
+    ExpectedBool,
+    ExpectedList,
+    ExpectedNumber,
+    ExpectedString,
+    ExpectedQuotation,
+    ProgramFinishedWithMultipleValues,
+    ModuleNotFound,
+    ImportCycle,
+    LimitExceeded,
+    IncompleteBinding,
+    NoMatch,
+    DivisionByZero,
+    InvalidEscape,
+    //`import`, `fun` or `precision` inside `pmap` or `pfilter`
+    ImpureOperation,
+    //A word that is neither a builtin, a function nor a local name, outside of the name of a `fun` or `struct`
+    UnknownWord,
+    InvalidSnapshot,
+    UnsupportedSnapshotVersion,
+    ExpectedRecord,
+    //`field` for a name the record does not have
+    NoSuchField,
+    //Lists and quotations nested deeper than MAX_NESTING
+    NestingTooDeep,
+    //A value in parentheses without its closing `)`
+    IncompleteValue,
+    //`toList`, `pmap` or `pfilter` on a sequence that has no `take` or `takeWhile` to end it
+    UnboundedSequence,
+    //`import` in a sandboxed interpreter, which may not read files
+    ImportRefused,
+}
+
+#[derive(Debug, Clone)]
+pub enum Datatype {
+    Int(i128),
+    Float(f64),
+    //Numerator and denominator in lowest terms, the denominator above 1
+    Rational(i128, i128),
+    Boolean(bool),
+    List(Vec<Datatype>),
+    String(String),
+    //The source of a quotation and the local names it refers to, captured when the quotation was created
+    Code(String, Env),
+    Symbol(String),
+    Sequence(Sequence),
+    //The qualified name of the struct and the fields in the order they were declared
+    Record(String, Env),
+}
+
+impl Datatype {
+    //The name `typeOf` gives and `case` matches
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Datatype::Int(_) => "int",
+            Datatype::Float(_) => "float",
+            Datatype::Rational(..) => "rational",
+            Datatype::Boolean(_) => "bool",
+            Datatype::String(_) => "string",
+            Datatype::List(_) => "list",
+            Datatype::Code(..) => "quotation",
+            Datatype::Symbol(_) => "symbol",
+            Datatype::Sequence(_) => "sequence",
+            Datatype::Record(..) => "record",
+        }
+    }
+}
+
+//Local names and their values, from `{ | a b | ... }` bindings or captured by a quotation
+pub type Env = Vec<(String, Datatype)>;
+
+//Bounds for running untrusted code, a program going past any of them fails with LimitExceeded
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    //Words executed in one top-level `execute`, copying a value or putting it inside another also costs a
+    //step for every value in it, and comparing values or moving the elements of a list a step for each
+    pub steps: u64,
+    //Values on the stack
+    pub stack: usize,
+    //Nested quotations and function calls
+    pub depth: usize,
+    //Elements of a list and bytes of a string
+    pub length: usize,
+}
+
+//Lists and quotations nest no deeper than this, in source and under limits also in values built at run time
+pub const MAX_NESTING : usize = 256;
+
+//The Rust function a quotation or function body is compiled to by `lab12 compile`
+pub type Compiled = fn(&mut Interpreter) -> Result<(), ProgramError>;
+
+//A user function defined with `fun`, remembering the module it was defined in
+#[derive(Clone)]
+struct Function {
+    body: String,
+    namespace: Option<String>,
+    env: Env,
+    //The compiled body, run instead of the source when the function was defined by a compiled program
+    native: Option<Compiled>,
+}
+
+pub struct Interpreter {
+    pub stack: Vec<Datatype>,
+    //Shared with the workers of `pmap` and the ones printing records, copied only when a function is defined
+    functions: Arc<BTreeMap<String, Function>>,
+    //Namespace of the module or function currently executing, unqualified names are looked up here first
+    namespace: Option<String>,
+    //Names bound by `{ | a b | ... }`, one frame per running quotation with bindings, innermost last
+    locals: Vec<Env>,
+    pub limits: Option<Limits>,
+    //Decimals printed for floats, None prints them in full
+    pub precision: Option<usize>,
+    steps: u64,
+    //In the workers of `pmap` and `pfilter` the steps of the caller and all of its workers together, and
+    //how many of the worker's own steps are counted in it already
+    #[cfg(feature = "std")]
+    shared_steps: Option<Arc<AtomicU64>>,
+    #[cfg(feature = "std")]
+    reported_steps: u64,
+    depth: usize,
+    //Set in the workers of `pmap` and `pfilter`, which may not change anything outside of their own stack
+    pure: bool,
+    //Set for programs from untrusted sources, such as those of the evaluation server, which may not read files
+    pub sandboxed: bool,
+    #[cfg(feature = "std")]
+    pub search_path: Vec<PathBuf>,
+    //Modules in the middle of being imported, used to detect import cycles
+    #[cfg(feature = "std")]
+    importing: Vec<PathBuf>,
+    #[cfg(feature = "std")]
+    imported: HashSet<PathBuf>,
+    //Modules embedded in a program by `lab12 compile`, by their path relative to the program
+    #[cfg(feature = "std")]
+    embedded: HashMap<PathBuf, String>,
+    #[cfg(feature = "std")]
+    pub tracer: Tracer,
+}
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        let mut interpreter = Interpreter::empty();
+        interpreter.load_prelude();
+        interpreter
+    }
+
+    //An interpreter without the prelude
+    fn empty() -> Interpreter {
+        Interpreter {
+            stack: Vec::new(),
+            functions: Arc::new(BTreeMap::new()),
+            namespace: None,
+            locals: Vec::new(),
+            limits: None,
+            precision: None,
+            steps: 0,
+            #[cfg(feature = "std")]
+            shared_steps: None,
+            #[cfg(feature = "std")]
+            reported_steps: 0,
+            depth: 0,
+            pure: false,
+            sandboxed: false,
+            #[cfg(feature = "std")]
+            search_path: Vec::new(),
+            #[cfg(feature = "std")]
+            importing: Vec::new(),
+            #[cfg(feature = "std")]
+            imported: HashSet::new(),
+            #[cfg(feature = "std")]
+            embedded: HashMap::new(),
+            #[cfg(feature = "std")]
+            tracer: Tracer::new(false, false),
+        }
+    }
+
+    //A pure interpreter with the same functions and local names, for running quotations in parallel.
+    //Its words are not traced
+    pub(crate) fn worker(&self) -> Interpreter {
+        let mut worker = Interpreter::empty();
+        worker.functions = Arc::clone(&self.functions);
+        worker.namespace = self.namespace.clone();
+        worker.locals = self.locals.clone();
+        worker.limits = self.limits;
+        worker.precision = self.precision;
+        worker.steps = self.steps;
+        worker.depth = self.depth;
+        worker.pure = true;
+        worker.sandboxed = self.sandboxed;
+        #[cfg(feature = "std")]
+        {
+            worker.search_path = self.search_path.clone();
+        }
+        worker
+    }
+
+    //Forgets the stack, user functions and imported modules, used between the tests
+    pub fn reset(&mut self) {
+        self.stack.clear();
+        self.functions = Arc::new(BTreeMap::new());
+        self.namespace = None;
+        self.locals.clear();
+        self.precision = None;
+        #[cfg(feature = "std")]
+        {
+            self.importing.clear();
+            self.imported.clear();
+        }
+        self.load_prelude();
+    }
+
+    //Runs a whole program, which has to leave exactly one value on the stack
+    pub fn run(&mut self, program : &str) -> Result<Datatype, ProgramError> {
+        self.stack.clear();
+        self.execute(program)?;
+
+        match self.stack.len() {
+            0 => Err(ProgramError::StackEmpty),
+            1 => self.pop(),
+            _ => Err(ProgramError::ProgramFinishedWithMultipleValues),
+        }
+    }
+
+    //Prints a value with the interpreter's float precision, and records with the `show` of their type
+    pub fn format(&self, value : Datatype) -> String {
+        format_styled(value, &Style { precision: self.precision, show: &|value| self.show(value) })
+    }
+
+    //Executes the code on the interpreter's stack
+    pub fn execute(&mut self, code : &str) -> Result<(), ProgramError> {
+        if self.depth == 0 {
+            self.steps = 0;
+        }
+        if self.limits.is_some_and(|limits| self.depth >= limits.depth) {
+            return Err(ProgramError::LimitExceeded);
+        }
+
+        self.depth += 1;
+        let result = self.execute_tokens(code);
+        self.depth -= 1;
+        result
+    }
+
+    fn execute_tokens(&mut self, code : &str) -> Result<(), ProgramError> {
+        let mut tokens: Vec<_> = tokenize(code);
+        tokens.reverse();
+
+        //Code starting with `| a b |` pops its arguments into local names, which are gone again when it finishes
+        if tokens.last() == Some(&"|") {
+            tokens.pop();
+            let frame = self.bind(&mut tokens)?;
+            self.locals.push(frame);
+            let result = self.run_tokens(&mut tokens);
+            self.locals.pop();
+            return result;
+        }
+        self.run_tokens(&mut tokens)
+    }
+
+    fn run_tokens(&mut self, tokens : &mut Vec<&str>) -> Result<(), ProgramError> {
+        while let Some(token) = tokens.pop() {
+            match datatype(token, tokens)? {
+                Some(value) => {
+                    let value = self.capture(value);
+                    if !self.locals.is_empty() {
+                        charge(&mut self.steps, self.limits, &value, 0)?;
+                    }
+                    self.stack.push(value);
+                },
+                None => self.run_word(token, tokens)?,
+            }
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    //Reads the names up to the closing `|`, the last name gets the top of the stack
+    fn bind(&mut self, tokens : &mut Vec<&str>) -> Result<Env, ProgramError> {
+        let mut names = Vec::new();
+        loop {
+            match tokens.pop() {
+                Some("|") => break,
+                Some(name) => names.push(name.to_string()),
+                None => return Err(ProgramError::IncompleteBinding),
+            }
+        }
+        self.pop_names(names)
+    }
+
+    //Pops a value for every name, the last name gets the top of the stack
+    fn pop_names(&mut self, names : Vec<String>) -> Result<Env, ProgramError> {
+        if self.stack.len() < names.len() {
+            return Err(ProgramError::StackEmpty);
+        }
+        let values = self.stack.split_off(self.stack.len() - names.len());
+        Ok(names.into_iter().zip(values).collect())
+    }
+
+    //Gives quotations the local names they refer to, so that they still see them when run somewhere else
+    fn capture(&self, value : Datatype) -> Datatype {
+        if self.locals.is_empty() {
+            return value;
+        }
+        match value {
+            Datatype::Code(code, mut env) => {
+                for token in tokenize(&code) {
+                    if env.iter().any(|(name, _)| name == token) {
+                        continue;
+                    }
+                    if let Some(value) = self.local(token) {
+                        env.push((token.to_string(), value));
+                    }
+                }
+                //Kept in order of name, so that equal closures compare and print the same
+                env.sort_by(|a, b| a.0.cmp(&b.0));
+                Datatype::Code(code, env)
+            },
+            Datatype::List(items) => Datatype::List(items.into_iter().map(|item| self.capture(item)).collect()),
+            value => value,
+        }
+    }
+
+    //The quotation after a word like `map`, with the local names it refers to
+    fn read_quotation(&mut self, tokens : &mut Vec<&str>) -> Result<Datatype, ProgramError> {
+        let quotation = self.capture(quotation(tokens)?);
+        if !self.locals.is_empty() {
+            charge(&mut self.steps, self.limits, &quotation, 1)?;
+        }
+        Ok(quotation)
+    }
+
+    //A list of values computed by a word like `map`, each of them now one level deeper
+    fn listed(&mut self, items : Vec<Datatype>) -> Result<Datatype, ProgramError> {
+        for item in &items {
+            charge(&mut self.steps, self.limits, item, 1)?;
+        }
+        Ok(Datatype::List(items))
+    }
+
+    //A copy of a value, charged against the limits like any other
+    fn copy(&mut self, value : &Datatype) -> Result<Datatype, ProgramError> {
+        charge(&mut self.steps, self.limits, value, 0)?;
+        Ok(value.clone())
+    }
+
+    //Adding or removing the first element of a list moves all of the others
+    fn shift(&mut self, list : &Datatype) -> Result<(), ProgramError> {
+        match list {
+            Datatype::List(items) => self.spend(items.len() as u64),
+            _ => Ok(()),
+        }
+    }
+
+    fn local(&self, name : &str) -> Option<Datatype> {
+        self.locals.iter().rev()
+            .find_map(|frame| frame.iter().rev().find(|(local, _)| local == name))
+            .map(|(_, value)| value.clone())
+    }
+
+    //Counts one step against the limits, every token and every run of a quotation is a step
+    fn step(&mut self) -> Result<(), ProgramError> {
+        self.spend(1)
+    }
+
+    //Counts the steps of a word doing more than one step of work, like comparing or moving many values
+    fn spend(&mut self, steps : u64) -> Result<(), ProgramError> {
+        self.steps += steps;
+        let steps = self.total_steps();
+        match self.limits {
+            Some(limits) if steps > limits.steps || self.stack.len() > limits.stack => Err(ProgramError::LimitExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    //The steps of the whole program, in a worker of `pmap` those of the caller and all of its workers
+    #[cfg(feature = "std")]
+    fn total_steps(&mut self) -> u64 {
+        match &self.shared_steps {
+            Some(shared) => {
+                let new = self.steps - self.reported_steps;
+                self.reported_steps = self.steps;
+                shared.fetch_add(new, AtomicOrdering::Relaxed) + new
+            },
+            None => self.steps,
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn total_steps(&mut self) -> u64 {
+        self.steps
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn run_word(&mut self, token : &str, tokens : &mut Vec<&str>) -> Result<(), ProgramError> {
+        self.word(token, tokens)
+    }
+
+    #[cfg(feature = "std")]
+    fn run_word(&mut self, token : &str, tokens : &mut Vec<&str>) -> Result<(), ProgramError> {
+        let frame = self.tracer.enter(token, &self.stack);
+        let result = self.word(token, tokens);
+        self.tracer.exit(token, frame, &self.stack);
+        result
+    }
+
+    fn word(&mut self, token : &str, tokens : &mut Vec<&str>) -> Result<(), ProgramError> {
+        //Local names hide builtins and functions of the same name
+        if let Some(value) = self.local(token) {
+            charge(&mut self.steps, self.limits, &value, 0)?;
+            self.stack.push(value);
+            return Ok(());
+        }
+        if self.pure && matches!(token, "import" | "fun" | "struct" | "precision") {
+            return Err(ProgramError::ImpureOperation);
+        }
+        if self.sandboxed && token == "import" {
+            return Err(ProgramError::ImportRefused);
+        }
+        if self.run_overload(token)? {
+            return Ok(());
+        }
+        let stack = &mut self.stack;
+
+        //Checking the input for operators and function-calls, returns Some, if there are anything to be pushed back in the stack
+        let result : Option<Result<Datatype, ProgramError>> = match token {
+            "+" => Some(add(pop(stack)?, pop(stack)?)),
+            "-" => Some(subtract(pop(stack)?, pop(stack)?)),
+            "*" => Some(multiply(pop(stack)?, pop(stack)?)),
+            "/" => Some(divide(pop(stack)?, pop(stack)?)),
+            "&&" => Some(and(pop(stack)?, pop(stack)?)),
+            "||" => Some(or(pop(stack)?, pop(stack)?)),
+            "not" => Some(not(pop(stack)?)),
+            "<" => Some(ordered(pop(stack)?, pop(stack)?, Ordering::is_lt)),
+            ">" => Some(ordered(pop(stack)?, pop(stack)?, Ordering::is_gt)),
+            "<=" => Some(ordered(pop(stack)?, pop(stack)?, Ordering::is_le)),
+            ">=" => Some(ordered(pop(stack)?, pop(stack)?, Ordering::is_ge)),
+            "div" => Some(div(pop(stack)?, pop(stack)?)),
+            "pow" => Some(pow(pop(stack)?, pop(stack)?)),
+            "min" => {
+                let top = pop(stack)?;
+                Some(self.min(top))
+            },
+            "max" => {
+                let top = pop(stack)?;
+                Some(self.max(top))
+            },
+            "abs" => Some(abs(pop(stack)?)),
+            "floor" => Some(floor(pop(stack)?)),
+            "ceil" => Some(ceil(pop(stack)?)),
+            "round" => Some(round(pop(stack)?)),
+            "sqrt" => Some(sqrt(pop(stack)?)),
+            "toInt" => Some(to_int(pop(stack)?)),
+            "toFloat" => Some(to_float(pop(stack)?)),
+            "numerator" => Some(numerator(pop(stack)?)),
+            "denominator" => Some(denominator(pop(stack)?)),
+            "precision" => {
+                let digits = pop(stack)?;
+                self.set_precision(digits)?;
+                None
+            },
+            "==" => Some(equal(pop(stack)?, pop(stack)?)),
+            "!=" => Some(not_equal(pop(stack)?, pop(stack)?)),
+            "swap" => {
+                let a = pop(stack)?;
+                let b = pop(stack)?;
+                stack.push(a);
+                stack.push(b);
+                None
+            },
+            "pop" => {
+                pop(stack)?;
+                None
+            },
+            "dup" => {
+                let a = pop(stack)?;
+                charge(&mut self.steps, self.limits, &a, 0)?;
+                stack.push(a.clone());
+                stack.push(a);
+                None
+            },
+            "over" => {
+                let i = below(stack, 2)?;
+                charge(&mut self.steps, self.limits, &stack[i], 0)?;
+                Some(Ok(stack[i].clone()))
+            },
+            "rot" => {
+                let i = below(stack, 3)?;
+                let a = stack.remove(i);
+                stack.push(a);
+                None
+            },
+            "nip" => {
+                let i = below(stack, 2)?;
+                stack.remove(i);
+                None
+            },
+            "tuck" => {
+                let i = below(stack, 2)?;
+                charge(&mut self.steps, self.limits, &stack[i + 1], 0)?;
+                let b = stack[i + 1].clone();
+                stack.insert(i, b);
+                None
+            },
+            "2dup" => {
+                let i = below(stack, 2)?;
+                for value in &stack[i..] {
+                    charge(&mut self.steps, self.limits, value, 0)?;
+                }
+                let pair = stack[i..].to_vec();
+                stack.extend(pair);
+                None
+            },
+            "depth" => Some(Ok(Datatype::Int(stack.len() as i128))),
+            "clear" => {
+                stack.clear();
+                None
+            },
+            "length" => Some(length(pop(stack)?)),
+            "splitWords" => Some(split_words(pop(stack)?)),
+            "typeOf" => Some(type_of(pop(stack)?)),
+            "words" => Some(Ok(self.words())),
+            "help" => {
+                let word = tokens.pop().ok_or(ProgramError::UnknownWord)?;
+                Some(self.help(word.strip_prefix('\'').filter(|word| !word.is_empty()).unwrap_or(word)))
+            },
+            "parseInteger" => Some(parse_integer(pop(stack)?)),
+            "parseFloat" => Some(parse_float(pop(stack)?)),
+            "empty" => Some(empty(pop(stack)?)),
+            "head" => Some(head(pop(stack)?)),
+            "tail" => {
+                let list = pop(stack)?;
+                self.shift(&list)?;
+                Some(tail(list))
+            },
+            "cons" => {
+                let list = pop(stack)?;
+                let item = pop(stack)?;
+                charge(&mut self.steps, self.limits, &item, 1)?;
+                self.shift(&list)?;
+                Some(cons(list, item))
+            },
+            "append" => {
+                let list = pop(stack)?;
+                let front = pop(stack)?;
+                self.shift(&list)?;
+                Some(append(list, front))
+            },
+            "sort" => {
+                let list = pop(stack)?;
+                Some(self.sort(list))
+            },
+            "dedup" => {
+                let list = pop(stack)?;
+                Some(self.dedup(list))
+            },
+            "take" => Some(take(pop(stack)?, pop(stack)?)),
+            "exec" => {
+                let code = pop(stack)?;
+                self.exec(code)?;
+                None
+            },
+            "map" => {
+                let list = pop(stack)?;
+                Some(self.map(list, tokens))
+            },
+            "pmap" => {
+                let list = pop(stack)?;
+                Some(self.pmap(list, tokens))
+            },
+            "pfilter" => {
+                let list = pop(stack)?;
+                Some(self.pfilter(list, tokens))
+            },
+            "filter" => {
+                let list = pop(stack)?;
+                Some(self.filter(list, tokens))
+            },
+            "takeWhile" => {
+                let list = pop(stack)?;
+                Some(self.take_while(list, tokens))
+            },
+            "iterate" => {
+                let seed = pop(stack)?;
+                charge(&mut self.steps, self.limits, &seed, 1)?;
+                Some(self.read_quotation(tokens).map(|next| Datatype::Sequence(Sequence::new(seed, next))))
+            },
+            "toList" => {
+                let sequence = pop(stack)?;
+                Some(self.to_list(sequence))
+            },
+            "if" => {
+                let predicate = pop(stack)?;
+                self.if_(predicate, tokens)?;
+                None
+            },
+            "case" => {
+                let value = pop(stack)?;
+                self.case(value, tokens)?;
+                None
+            },
+            "each" => {
+                let list = pop(stack)?;
+                self.each(list, tokens)?;
+                None
+            },
+            "foldl" => {
+                let init_accumulator = pop(stack)?;
+                let list = pop(stack)?;
+                Some(self.foldl(init_accumulator, list, tokens))
+            },
+            "times" => {
+                let number = pop(stack)?;
+                self.times(number, tokens)?;
+                None
+            },
+            "fun" => {
+                let body = pop(stack)?;
+                let name = pop(stack)?;
+                self.define(name, body)?;
+                None
+            },
+            "struct" => {
+                let fields = pop(stack)?;
+                let name = pop(stack)?;
+                self.define_struct(name, fields)?;
+                None
+            },
+            "record" => {
+                let fields = pop(stack)?;
+                let tag = pop(stack)?;
+                Some(self.record(tag, fields))
+            },
+            "field" => Some(field(pop(stack)?, pop(stack)?)),
+            "import" => {
+                let path = module_path(tokens)?;
+                self.import(&path)?;
+                None
+            },
+            _ => match self.resolve(token) {
+                Some(name) => {
+                    self.call(&name)?;
+                    None
+                },
+                //An unknown word is only a name where it is followed by the body of a `fun` or the fields of a `struct`
+                None if names_definition(tokens) => Some(Ok(Datatype::Symbol(token.to_string()))),
+                None => Some(Err(ProgramError::UnknownWord)),
+            },
+        };
+
+        match result {
+            Some(Ok(value)) if self.limits.is_some_and(|limits| too_long(&value, limits.length)) => return Err(ProgramError::LimitExceeded),
+            Some(Ok(value)) => self.stack.push(value),
+            Some(Err(e)) => return Err(e),
+            None => (),
+        }
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<Datatype, ProgramError> {
+        pop(&mut self.stack)
+    }
+
+    pub fn set_precision(&mut self, digits : Datatype) -> Result<(), ProgramError> {
+        match digits {
+            //At least one decimal, like `--precision`, so that floats keep their decimal point
+            Datatype::Int(digits) if digits > 0 => {
+                self.precision = Some(usize::try_from(digits).map_err(|_| ProgramError::InvalidOperation)?);
+                Ok(())
+            },
+            Datatype::Int(_) => Err(ProgramError::InvalidOperation),
+            _ => Err(ProgramError::ExpectedNumber),
+        }
+    }
+
+    //Quotations are executed and quoted words called, any other value given in place of a quotation is pushed as it is
+    fn run_quotation(&mut self, quotation : &Datatype) -> Result<(), ProgramError> {
+        self.step()?;
+        match quotation {
+            //Only the names the quotation captured are visible, not those of the code running it
+            Datatype::Code(code, env) => {
+                let env = frame(&mut self.steps, self.limits, env)?;
+                let locals = core::mem::replace(&mut self.locals, env);
+                let result = self.execute(code);
+                self.locals = locals;
+                result
+            },
+            //A quoted word runs on its own, so words like `map` that read the code after them have nothing to read
+            Datatype::Symbol(name) => self.run_word(name, &mut Vec::new()),
+            value => {
+                charge(&mut self.steps, self.limits, value, 0)?;
+                self.stack.push(value.clone());
+                Ok(())
+            },
+        }
+    }
+
+    pub fn exec(&mut self, code : Datatype) -> Result<(), ProgramError> {
+        match code {
+            Datatype::Code(..) | Datatype::Symbol(_) => self.run_quotation(&code),
+            _ => Err(ProgramError::ExpectedQuotation),
+        }
+    }
+
+    pub fn define(&mut self, name : Datatype, body : Datatype) -> Result<(), ProgramError> {
+        self.define_function(name, body, None)
+    }
+
+    fn define_function(&mut self, name : Datatype, body : Datatype, native : Option<Compiled>) -> Result<(), ProgramError> {
+        match (name, body) {
+            (Datatype::Symbol(name), Datatype::Code(body, env)) => {
+                //Functions defined while importing a module end up in the module's namespace
+                let name = match &self.namespace {
+                    Some(namespace) => format!("{}.{}", namespace, name),
+                    None => name,
+                };
+                Arc::make_mut(&mut self.functions).insert(name, Function { body, namespace: self.namespace.clone(), env, native });
+                Ok(())
+            },
+            (_, Datatype::Code(..)) => Err(ProgramError::InvalidOperation),
+            _ => Err(ProgramError::ExpectedQuotation),
+        }
+    }
+
+    //The builtins in the order of the builtin table, then the functions by name
+    pub fn words(&self) -> Datatype {
+        let builtins = BUILTINS.iter().map(|builtin| builtin.name.to_string());
+        let functions = self.functions.keys().cloned();
+        Datatype::List(builtins.chain(functions).map(Datatype::Symbol).collect())
+    }
+
+    //The stack effect and description of a builtin, or the body of a function
+    pub fn help(&self, word : &str) -> Result<Datatype, ProgramError> {
+        if let Some(builtin) = builtin(word) {
+            return Ok(Datatype::String(format!("{} {} {}", builtin.name, builtin.effect, builtin.description)));
+        }
+        let name = self.resolve(word).ok_or(ProgramError::UnknownWord)?;
+        Ok(Datatype::String(format!("{} user function {}", name, format_value(Datatype::Code(self.functions[&name].body.clone(), Vec::new()), None))))
+    }
+
+    //Finds the function a word refers to, preferring the namespace of the code that is running
+    fn resolve(&self, word : &str) -> Option<String> {
+        if let Some(namespace) = &self.namespace {
+            let qualified = format!("{}.{}", namespace, word);
+            if self.functions.contains_key(&qualified) {
+                return Some(qualified);
+            }
+        }
+        if self.functions.contains_key(word) {
+            Some(word.to_string())
+        } else {
+            None
+        }
+    }
+
+    fn call(&mut self, name : &str) -> Result<(), ProgramError> {
+        let function = &self.functions[name];
+        let native = function.native;
+        let body = if native.is_some() { String::new() } else { function.body.clone() };
+        let env = frame(&mut self.steps, self.limits, &function.env)?;
+        let namespace = core::mem::replace(&mut self.namespace, function.namespace.clone());
+        //A function only sees its own local names and those captured by its body, not those of its caller
+        let locals = core::mem::replace(&mut self.locals, env);
+
+        let result = match native {
+            Some(native) => native(self),
+            None => self.execute(&body),
+        };
+        self.namespace = namespace;
+        self.locals = locals;
+        result
+    }
+
+    fn foldl(&mut self, init_accumulator : Datatype, list : Datatype, tokens : &mut Vec<&str>) -> Result<Datatype, ProgramError> {
+        let operation = self.read_quotation(tokens)?;
+
+        let iterable_list = match list {
+            Datatype::List(list) => list,
+            _ => return Err(ProgramError::ExpectedList),
+        };
+
+        let mut final_accumulation = init_accumulator;
+        for item in iterable_list {
+            //Does operation to the accumulated value with the list item as argument
+            self.stack.push(final_accumulation);
+            self.stack.push(item);
+            self.run_quotation(&operation)?;
+            final_accumulation = self.pop()?;
+        }
+
+        Ok(final_accumulation)
+    }
+
+    fn each(&mut self, list : Datatype, tokens : &mut Vec<&str>) -> Result<(), ProgramError> {
+        let operation = self.read_quotation(tokens)?;
+
+        let list = match list {
+            Datatype::List(list) => list,
+            _ => return Err(ProgramError::InvalidOperation),
+        };
+
+        //Leaves the result for every element on the stack
+        for item in list {
+            self.stack.push(item);
+            self.run_quotation(&operation)?;
+        }
+        Ok(())
+    }
+
+    fn if_(&mut self, predicate : Datatype, tokens : &mut Vec<&str>) -> Result<(), ProgramError> {
+        let true_expression = self.read_quotation(tokens)?;
+        let false_expression = self.read_quotation(tokens)?;
+
+        match predicate {
+            Datatype::Boolean(true) => self.run_quotation(&true_expression),
+            Datatype::Boolean(false) => self.run_quotation(&false_expression),
+            _ => Err(ProgramError::ExpectedBool)
+        }
+    }
+
+    fn map(&mut self, list : Datatype, tokens : &mut Vec<&str>) -> Result<Datatype, ProgramError> {
+        let operation = self.read_quotation(tokens)?;
+
+        match list {
+            Datatype::List(list) => {
+                let mut new_list = Vec::new();
+                for item in list {
+                    self.stack.push(item);
+                    self.run_quotation(&operation)?;
+                    new_list.push(self.pop()?);
+                }
+                self.listed(new_list)
+            }
+            Datatype::Sequence(sequence) => Ok(sequence.then(Step::Map(operation))),
+            _ => Err(ProgramError::ExpectedList),
+        }
+    }
+
+    fn filter(&mut self, list : Datatype, tokens : &mut Vec<&str>) -> Result<Datatype, ProgramError> {
+        let predicate = self.read_quotation(tokens)?;
+
+        match list {
+            Datatype::List(list) => {
+                let mut new_list = Vec::new();
+                for item in list {
+                    if self.keeps(&predicate, &item)? {
+                        new_list.push(item);
+                    }
+                }
+                Ok(Datatype::List(new_list))
+            },
+            Datatype::Sequence(sequence) => Ok(sequence.then(Step::Filter(predicate))),
+            _ => Err(ProgramError::ExpectedList),
+        }
+    }
+
+    fn take_while(&mut self, list : Datatype, tokens : &mut Vec<&str>) -> Result<Datatype, ProgramError> {
+        let predicate = self.read_quotation(tokens)?;
+
+        match list {
+            Datatype::List(list) => {
+                let mut new_list = Vec::new();
+                for item in list {
+                    if !self.keeps(&predicate, &item)? {
+                        break;
+                    }
+                    new_list.push(item);
+                }
+                Ok(Datatype::List(new_list))
+            },
+            Datatype::Sequence(sequence) => Ok(sequence.then(Step::TakeWhile(predicate))),
+            _ => Err(ProgramError::ExpectedList),
+        }
+    }
+
+    //The larger of two numbers, or the largest number of a list on top of the stack
+    pub fn max(&mut self, top : Datatype) -> Result<Datatype, ProgramError> {
+        self.extreme(top, max)
+    }
+
+    //The smaller of two numbers, or the smallest number of a list on top of the stack
+    pub fn min(&mut self, top : Datatype) -> Result<Datatype, ProgramError> {
+        self.extreme(top, min)
+    }
+
+    //Every comparison in a list is a step
+    fn extreme(&mut self, top : Datatype, pick : fn(Datatype, Datatype) -> Result<Datatype, ProgramError>) -> Result<Datatype, ProgramError> {
+        match top {
+            Datatype::List(list) => {
+                self.spend(list.len() as u64)?;
+                let first = list.first().cloned().ok_or(ProgramError::InvalidOperation)?;
+                list.into_iter().try_fold(first, |extreme, item| pick(item, extreme))
+            },
+            top => {
+                let below = self.pop()?;
+                pick(top, below)
+            },
+        }
+    }
+
+    //Every comparison is a step
+    pub fn sort(&mut self, list : Datatype) -> Result<Datatype, ProgramError> {
+        let mut list = match list {
+            Datatype::List(list) => list,
+            _ => return Err(ProgramError::ExpectedList),
+        };
+        let mut comparisons = 0;
+        list.sort_by(|a, b| {
+            comparisons += 1;
+            compare(a, b)
+        });
+        self.spend(comparisons)?;
+        Ok(Datatype::List(list))
+    }
+
+    //Keeps the first of every group of elements equal by `==`, every comparison is a step
+    pub fn dedup(&mut self, list : Datatype) -> Result<Datatype, ProgramError> {
+        let list = match list {
+            Datatype::List(list) => list,
+            _ => return Err(ProgramError::ExpectedList),
+        };
+        if !list.iter().any(|item| self.defined(item, "==").is_some()) {
+            return self.dedup_sorted(list);
+        }
+
+        //An `==` of a record type need not agree with the order, so every element is compared with those kept
+        let mut unique : Vec<Datatype> = Vec::new();
+        for item in list {
+            let mut repeated = false;
+            for kept in &unique {
+                self.step()?;
+                if self.equal(kept, &item)? {
+                    repeated = true;
+                    break;
+                }
+            }
+            if !repeated {
+                unique.push(item);
+            }
+        }
+        Ok(Datatype::List(unique))
+    }
+
+    //Without overloads `==` is the order's equality, so equal elements end up next to each other when sorted.
+    //The sort is stable, which puts the first of them in front
+    fn dedup_sorted(&mut self, list : Vec<Datatype>) -> Result<Datatype, ProgramError> {
+        let mut order : Vec<usize> = (0..list.len()).collect();
+        let mut comparisons = list.len() as u64;
+        order.sort_by(|&a, &b| {
+            comparisons += 1;
+            compare(&list[a], &list[b])
+        });
+        self.spend(comparisons)?;
+
+        let mut keep = vec![false; list.len()];
+        for (position, &index) in order.iter().enumerate() {
+            keep[index] = position == 0 || compare(&list[order[position - 1]], &list[index]).is_ne();
+        }
+        Ok(Datatype::List(list.into_iter().zip(keep).filter(|(_, keep)| *keep).map(|(item, _)| item).collect()))
+    }
+
+    //Runs a sequence to the end, a list is already one
+    pub fn to_list(&mut self, sequence : Datatype) -> Result<Datatype, ProgramError> {
+        match sequence {
+            Datatype::Sequence(sequence) => self.force(sequence).and_then(|items| self.listed(items)),
+            list @ Datatype::List(_) => Ok(list),
+            _ => Err(ProgramError::ExpectedList),
+        }
+    }
+
+    fn times(&mut self, number : Datatype, tokens : &mut Vec<&str>) -> Result<(), ProgramError> {
+        let operation = self.read_quotation(tokens)?;
+
+        match number {
+            Datatype::Int(value) => {
+                for _ in 0..value {
+                    self.run_quotation(&operation)?;
+                }
+                Ok(())
+            },
+            _ => Err(ProgramError::InvalidOperation),
+        }
+    }
+}
+
+fn pop(stack : &mut Vec<Datatype>) -> Result<Datatype, ProgramError> {
+    stack.pop().ok_or(ProgramError::StackEmpty)
+}
+
+//Under limits a value copied, or put inside another at the given depth, costs a step for every value in it
+//and may not end up nested deeper than MAX_NESTING, so that neither the memory it takes nor the recursion
+//in printing and dropping it can grow past what the limits allow
+fn charge(steps : &mut u64, limits : Option<Limits>, value : &Datatype, depth : usize) -> Result<(), ProgramError> {
+    let limits = match limits {
+        Some(limits) => limits,
+        None => return Ok(()),
+    };
+    let (size, nesting) = measure(value, limits.steps.saturating_sub(*steps));
+    *steps += size;
+    if *steps > limits.steps {
+        Err(ProgramError::LimitExceeded)
+    } else if depth + nesting > MAX_NESTING {
+        Err(ProgramError::NestingTooDeep)
+    } else {
+        Ok(())
+    }
+}
+
+//The local names a quotation or function captured, copied for one run of it and charged like any copy
+fn frame(steps : &mut u64, limits : Option<Limits>, env : &Env) -> Result<Vec<Env>, ProgramError> {
+    if env.is_empty() {
+        return Ok(Vec::new());
+    }
+    for (_, value) in env {
+        charge(steps, limits, value, 0)?;
+    }
+    Ok(vec![env.clone()])
+}
+
+//The values in a value, counting every byte of a string or quotation, and how deep the deepest of them
+//is nested. It walks the value without recursion and stops once the size is past the budget
+fn measure(value : &Datatype, budget : u64) -> (u64, usize) {
+    let mut size : u64 = 0;
+    let mut nesting = 0;
+    let mut pending = vec![(value, 0)];
+    while let Some((value, depth)) = pending.pop() {
+        size += 1;
+        nesting = nesting.max(depth);
+        if size > budget {
+            break;
+        }
+        match value {
+            Datatype::List(items) => pending.extend(items.iter().map(|item| (item, depth + 1))),
+            Datatype::String(text) | Datatype::Symbol(text) => size += text.len() as u64,
+            Datatype::Code(code, env) => {
+                size += code.len() as u64;
+                pending.extend(env.iter().map(|(_, value)| (value, depth + 1)));
+            },
+            Datatype::Record(_, fields) => pending.extend(fields.iter().map(|(_, value)| (value, depth + 1))),
+            Datatype::Sequence(sequence) => {
+                pending.push((&sequence.seed, depth + 1));
+                pending.push((&sequence.next, depth + 1));
+                pending.extend(sequence.steps.iter().filter_map(|step| match step {
+                    Step::Map(quotation) | Step::Filter(quotation) | Step::TakeWhile(quotation) => Some((quotation, depth + 1)),
+                    Step::Take(_) => None,
+                }));
+            },
+            _ => (),
+        }
+    }
+    (size, nesting)
+}
+
+//Lists and strings longer than the limit, checked on every value a word gives, so that a list doubled
+//over and over runs into the limit long before it fills the memory
+fn too_long(value : &Datatype, length : usize) -> bool {
+    match value {
+        Datatype::List(items) => items.len() > length,
+        Datatype::String(text) => text.len() > length,
+        _ => false,
+    }
+}
+
+//Index of the count-th element from the top, for the words rearranging the top of the stack
+fn below(stack : &[Datatype], count : usize) -> Result<usize, ProgramError> {
+    stack.len().checked_sub(count).ok_or(ProgramError::StackEmpty)
+}
+
+//Reads the quotation following words like `map` and `if`, a single word or value may be given without braces
+fn quotation(tokens : &mut Vec<&str>) -> Result<Datatype, ProgramError> {
+    nested_quotation(tokens, 0)
+}
+
+fn nested_quotation(tokens : &mut Vec<&str>, depth : usize) -> Result<Datatype, ProgramError> {
+    let token = tokens.pop().ok_or(ProgramError::ExpectedQuotation)?;
+    match nested(token, tokens, depth)? {
+        Some(value) => Ok(value),
+        None => Ok(Datatype::Code(token.to_string(), Vec::new())),
+    }
+}
+
+//Whether the next tokens are a value followed by `fun` or `struct`, which makes the word before them the name
+//being defined. Strings are single tokens, so only brackets need to be balanced
+fn names_definition(tokens : &[&str]) -> bool {
+    let mut rest = tokens.iter().rev();
+    match rest.next() {
+        Some(&("{" | "[" | "(")) => {
+            let mut depth = 1;
+            while depth > 0 {
+                match rest.next() {
+                    Some(&("{" | "[" | "(")) => depth += 1,
+                    Some(&("}" | "]" | ")")) => depth -= 1,
+                    Some(_) => (),
+                    None => return false,
+                }
+            }
+        },
+        Some(token) if token.starts_with('\'') => (),
+        _ => return false,
+    }
+    matches!(rest.next(), Some(&("fun" | "struct")))
+}
+
+//The module after `import`, either a string or a single token like "lib.bprog"
+fn module_path(tokens : &mut Vec<&str>) -> Result<String, ProgramError> {
+    let token = tokens.pop().ok_or(ProgramError::ModuleNotFound)?;
+    match datatype(token, tokens)? {
+        Some(Datatype::String(path)) => Ok(path),
+        Some(_) => Err(ProgramError::ExpectedString),
+        None => Ok(token.trim_matches('"').to_string()),
+    }
+}
+//The value a token starts, None for a word. Lists, strings and quotations read their tokens up to the closing one
+fn datatype(token: &str, tokens: &mut Vec<&str>) -> Result<Option<Datatype>, ProgramError> {
+    nested(token, tokens, 0)
+}
+
+//Like datatype, inside as many lists and quotations as depth says. Reading them does not recurse past
+//MAX_NESTING, so that deeply nested input fails instead of running out of stack
+fn nested(token: &str, tokens: &mut Vec<&str>, depth : usize) -> Result<Option<Datatype>, ProgramError> {
+    if matches!(token, "{" | "[" | "(") && depth >= MAX_NESTING {
+        return Err(ProgramError::NestingTooDeep);
+    }
+    let value = match token {
+        "{" => code(tokens, depth + 1)?,
+        "[" => list(tokens, depth + 1)?,
+        "(" => constructed(tokens, depth + 1)?,
+        "False" => Datatype::Boolean(false),
+        "True" => Datatype::Boolean(true),
+        //Printed non-finite floats, the only floats without a decimal point
+        "NaN" => Datatype::Float(f64::NAN),
+        "inf" => Datatype::Float(f64::INFINITY),
+        "-inf" => Datatype::Float(f64::NEG_INFINITY),
+        _ if token.starts_with('"') => string(token)?,
+        _ => match (token.parse::<i128>(), token.parse::<f64>(), fraction(token)) {
+            (Ok(value), _, _) => Datatype::Int(value),
+            (_, Ok(value), _) if token.contains('.') => Datatype::Float(value),
+            //A rational like 1/3 or -2/4, which is an int when it divides evenly
+            (_, _, Some((n, d))) => match rational(n, d)? {
+                Some(value) => value,
+                None => return Ok(None),
+            },
+            //'word pushes the word itself instead of running it
+            _ => match token.strip_prefix('\'') {
+                Some(name) if !name.is_empty() => Datatype::Symbol(name.to_string()),
+                _ => return Ok(None),
+            },
+        },
+    };
+    Ok(Some(value))
+}
+
+//Numerator and denominator of a token like 1/3, the denominator written without a sign
+fn fraction(token : &str) -> Option<(i128, i128)> {
+    let (n, d) = token.split_once('/')?;
+    if !d.starts_with(|c : char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((n.parse().ok()?, d.parse().ok()?))
+}
+
+fn list(tokens: &mut Vec<&str>, depth : usize) -> Result<Datatype, ProgramError> {
+    let mut list_ : Vec<Datatype> = Vec::new();
+
+    //Looping over all the list elements till the closing bracket
+    loop {
+
+        //If the token is a list, call the list function recursively
+        let new_token = tokens.pop().ok_or(ProgramError::IncompleteList)?;
+        if new_token == "]" {
+            return Ok(Datatype::List(list_));
+        }
+        //A list is data, so a word in it is kept as a name the way 'word would be
+        match nested(new_token, tokens, depth)? {
+            Some(value) => list_.push(value),
+            None => list_.push(Datatype::Symbol(new_token.to_string())),
+        }
+    }
+}
+
+//A value without a literal of its own, written between parentheses as the words that build it, like
+//`( 1 iterate { 2 * } 5 take )`. Only the values in it are read, none of its words run
+fn constructed(tokens: &mut Vec<&str>, depth : usize) -> Result<Datatype, ProgramError> {
+    let mut values = Vec::new();
+    let value = loop {
+        let token = tokens.pop().ok_or(ProgramError::IncompleteValue)?;
+        if let Some(value) = nested(token, tokens, depth)? {
+            values.push(value);
+            continue;
+        }
+        break match (token, values.pop()) {
+            ("iterate", Some(seed)) if values.is_empty() => sequence::read(seed, tokens, depth)?,
+            ("exec", Some(Datatype::Code(binding, env))) if env.is_empty() => closure(&binding, values, depth)?,
+            ("record", Some(fields @ Datatype::List(_))) => records::read(values, fields)?,
+            _ => return Err(ProgramError::InvalidOperation),
+        };
+    };
+    match tokens.pop() {
+        Some(")") => Ok(value),
+        Some(_) => Err(ProgramError::InvalidOperation),
+        None => Err(ProgramError::IncompleteValue),
+    }
+}
+
+//The closure `( v1 .. vn { | n1 .. nn | { body } } exec )`, in which every name gets its value
+fn closure(binding : &str, values : Vec<Datatype>, depth : usize) -> Result<Datatype, ProgramError> {
+    let mut tokens = tokenize(binding);
+    tokens.reverse();
+    if tokens.pop() != Some("|") {
+        return Err(ProgramError::InvalidOperation);
+    }
+    let mut names = Vec::new();
+    loop {
+        match tokens.pop() {
+            Some("|") => break,
+            Some(name) => names.push(name.to_string()),
+            None => return Err(ProgramError::IncompleteBinding),
+        }
+    }
+    let body = match tokens.pop() {
+        Some("{") => code(&mut tokens, depth + 1)?,
+        _ => return Err(ProgramError::InvalidOperation),
+    };
+    if !tokens.is_empty() || names.len() != values.len() {
+        return Err(ProgramError::InvalidOperation);
+    }
+
+    let mut env : Env = names.into_iter().zip(values).collect();
+    env.sort_by(|a, b| a.0.cmp(&b.0));
+    match body {
+        Datatype::Code(code, _) => Ok(Datatype::Code(code, env)),
+        _ => Err(ProgramError::ExpectedQuotation),
+    }
+}
+
+//A string literal token like `" a \"b\" "`. The space after the opening quote and the one before the closing
+//quote only set the string apart and are not part of it, any other whitespace is kept
+fn string(token : &str) -> Result<Datatype, ProgramError> {
+    if !is_closed_string(token) {
+        return Err(ProgramError::IncompleteString);
+    }
+    let text = &token[1..token.len() - 1];
+    let text = text.strip_prefix(' ').unwrap_or(text);
+    let text = text.strip_suffix(' ').unwrap_or(text);
+
+    let mut string_ = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            string_.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => string_.push('"'),
+            Some('\\') => string_.push('\\'),
+            Some('n') => string_.push('\n'),
+            Some('t') => string_.push('\t'),
+            Some('r') => string_.push('\r'),
+            Some('u') => string_.push(unicode_escape(&mut chars)?),
+            _ => return Err(ProgramError::InvalidEscape),
+        }
+    }
+    Ok(Datatype::String(string_))
+}
+
+//The character of a `\u{1F600}` escape, read after the `u`
+fn unicode_escape(chars : &mut core::str::Chars) -> Result<char, ProgramError> {
+    if chars.next() != Some('{') {
+        return Err(ProgramError::InvalidEscape);
+    }
+    let mut digits = String::new();
+    for c in chars.by_ref() {
+        if c == '}' {
+            return u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32).ok_or(ProgramError::InvalidEscape);
+        }
+        digits.push(c);
+    }
+    Err(ProgramError::InvalidEscape)
+}
+
+fn code(tokens: &mut Vec<&str>, depth : usize) -> Result<Datatype, ProgramError> {
+    let mut code_ = String::new();
+    let mut new_token = tokens.pop().ok_or(ProgramError::IncompleteQuotation)?;
+    while new_token != "}" {
+        if new_token == "{" {
+            if depth >= MAX_NESTING {
+                return Err(ProgramError::NestingTooDeep);
+            }
+            let inner_code = format_stack_item(code(tokens, depth + 1)?);
+            code_.push_str(format!(" {}", inner_code).as_str());
+        } else {
+            code_.push_str(format!(" {}", new_token).as_str());
+        }
+        new_token = tokens.pop().ok_or(ProgramError::IncompleteQuotation)?;
+    }
+    Ok(Datatype::Code(code_.trim().to_string(), Vec::new()))
+}
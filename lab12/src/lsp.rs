@@ -0,0 +1,237 @@
+//Language server for bprog: JSON-RPC messages with LSP framing over any reader and writer
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, Write};
+
+use serde_json::{json, Value};
+
+use crate::builtins::{builtin, BUILTINS};
+use crate::lexer::{definitions, lex, unbalanced, Token};
+use crate::PRELUDE;
+
+const PARSE_ERROR : i64 = -32700;
+const INVALID_REQUEST : i64 = -32600;
+const METHOD_NOT_FOUND : i64 = -32601;
+//Far above any document worth editing, larger messages are skipped without being read into memory
+const MAX_MESSAGE : usize = 16 << 20;
+
+//A message read from the client, or the error to answer a body that could not be used with
+enum Message {
+    Valid(Value),
+    Invalid(i64, String),
+}
+
+struct Server {
+    //Open documents by URI, kept in full since the server asks for full text sync
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+//Serves requests until `exit` or the end of input, returns whether `shutdown` was requested first
+pub fn serve(input : &mut impl BufRead, output : &mut impl Write) -> io::Result<bool> {
+    let mut server = Server { documents: HashMap::new(), shutdown: false };
+
+    while let Some(message) = read_message(input)? {
+        //Without a readable id the error goes out with a null one, and the server carries on with the next message
+        let message = match message {
+            Message::Valid(message) => message,
+            Message::Invalid(code, error) => {
+                write_message(output, &json!({ "jsonrpc": "2.0", "id": Value::Null, "error": { "code": code, "message": error } }))?;
+                continue;
+            },
+        };
+        let method = message["method"].as_str().unwrap_or_default();
+        if method == "exit" {
+            break;
+        }
+
+        let params = &message["params"];
+        let result = match method {
+            "initialize" => Some(initialize()),
+            "shutdown" => {
+                server.shutdown = true;
+                Some(Value::Null)
+            },
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                server.documents.insert(uri.to_string(), text.to_string());
+                write_message(output, &diagnostics(uri, text))?;
+                None
+            },
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                //With full sync the last change holds the whole document
+                if let Some(text) = params["contentChanges"].as_array().and_then(|changes| changes.last()).and_then(|change| change["text"].as_str()) {
+                    server.documents.insert(uri.to_string(), text.to_string());
+                    write_message(output, &diagnostics(uri, text))?;
+                }
+                None
+            },
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                server.documents.remove(uri);
+                write_message(output, &diagnostics(uri, ""))?;
+                None
+            },
+            "textDocument/hover" => Some(server.with_token(params, hover)),
+            "textDocument/completion" => Some(server.completion(params)),
+            "textDocument/definition" => Some(server.with_token(params, definition)),
+            _ => None,
+        };
+
+        //Notifications have no id and get no answer
+        let id = &message["id"];
+        if id.is_null() {
+            continue;
+        }
+        let response = match result {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": METHOD_NOT_FOUND, "message": format!("Unknown method: {}", method) },
+            }),
+        };
+        write_message(output, &response)?;
+    }
+
+    Ok(server.shutdown)
+}
+
+fn initialize() -> Value {
+    json!({
+        "capabilities": {
+            "textDocumentSync": 1,
+            "hoverProvider": true,
+            "completionProvider": {},
+            "definitionProvider": true,
+        },
+        "serverInfo": { "name": "bprog-lsp" },
+    })
+}
+
+impl Server {
+    //Looks up the token under the cursor of a position request and hands it to the handler
+    fn with_token(&self, params : &Value, handler : fn(&str, &[Token], &Token) -> Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let text = match self.documents.get(uri) {
+            Some(text) => text,
+            None => return Value::Null,
+        };
+        let line = params["position"]["line"].as_u64().unwrap_or_default() as usize;
+        let character = params["position"]["character"].as_u64().unwrap_or_default() as usize;
+
+        let tokens = lex(text);
+        match tokens.iter().find(|token| token.line == line && token.column <= character && character <= token.end_column()) {
+            Some(token) => handler(uri, &tokens, &token.unquoted()),
+            None => Value::Null,
+        }
+    }
+
+    fn completion(&self, params : &Value) -> Value {
+        let mut items : Vec<Value> = BUILTINS.iter()
+            .map(|builtin| json!({ "label": builtin.name, "kind": 14, "detail": builtin.effect, "documentation": builtin.description }))
+            .collect();
+
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let text = self.documents.get(uri).map(String::as_str).unwrap_or_default();
+        let prelude = lex(PRELUDE);
+        let document = lex(text);
+        let mut names : Vec<&str> = definitions(&prelude).iter().chain(definitions(&document).iter()).map(|token| token.text).collect();
+        names.sort();
+        names.dedup();
+        for name in names {
+            items.push(json!({ "label": name, "kind": 3, "detail": "user function" }));
+        }
+        json!(items)
+    }
+}
+
+fn range(token : &Token) -> Value {
+    json!({
+        "start": { "line": token.line, "character": token.column },
+        "end": { "line": token.line, "character": token.end_column() },
+    })
+}
+
+fn hover(_uri : &str, tokens : &[Token], token : &Token) -> Value {
+    let contents = if let Some(builtin) = builtin(token.text) {
+        format!("`{}` `{}`\n\n{}", builtin.name, builtin.effect, builtin.description)
+    } else if let Some(name) = definitions(tokens).into_iter().find(|name| name.text == token.text) {
+        format!("`{}` user function, defined on line {}", name.text, name.line + 1)
+    } else {
+        return Value::Null;
+    };
+    json!({ "contents": { "kind": "markdown", "value": contents }, "range": range(token) })
+}
+
+fn definition(uri : &str, tokens : &[Token], token : &Token) -> Value {
+    match definitions(tokens).into_iter().find(|name| name.text == token.text) {
+        Some(name) => json!({ "uri": uri, "range": range(&name) }),
+        None => Value::Null,
+    }
+}
+
+fn diagnostics(uri : &str, text : &str) -> Value {
+    let problems : Vec<Value> = unbalanced(&lex(text)).into_iter()
+        .map(|(token, error)| {
+            let message = match token.text {
+                text if text.starts_with('"') => "String is never closed".to_string(),
+                "{" | "[" | "(" => format!("`{}` is never closed", token.text),
+                _ => format!("`{}` has no matching opening bracket", token.text),
+            };
+            json!({ "range": range(&token), "severity": 1, "source": "bprog", "code": format!("{:?}", error), "message": message })
+        })
+        .collect();
+
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": problems },
+    })
+}
+
+fn read_message(input : &mut impl BufRead) -> io::Result<Option<Message>> {
+    let mut length : Option<usize> = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        //Header names are case-insensitive
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+
+    //Without a length the end of the body is where the JSON value in it ends, which is read and dropped
+    let Some(length) = length else {
+        return Ok(Some(match serde_json::Deserializer::from_reader(input).into_iter::<Value>().next() {
+            Some(Err(e)) if e.is_io() => return Err(e.into()),
+            None => return Ok(None),
+            _ => Message::Invalid(INVALID_REQUEST, "Missing or invalid Content-Length header".to_string()),
+        }));
+    };
+    if length > MAX_MESSAGE {
+        io::copy(&mut input.take(length as u64), &mut io::sink())?;
+        return Ok(Some(Message::Invalid(INVALID_REQUEST, format!("Message of {} bytes is larger than the limit of {}", length, MAX_MESSAGE))));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(match serde_json::from_slice(&body) {
+        Ok(message) => Message::Valid(message),
+        Err(e) => Message::Invalid(PARSE_ERROR, format!("Parse error: {}", e)),
+    }))
+}
+
+fn write_message(output : &mut impl Write, message : &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
@@ -1,695 +1,232 @@
-use std::fmt::Write;
 use std::io;
-
-#[derive(Debug)]
-enum ProgramError {
-    InvalidOperation,
-    IncompleteList,
-    IncompleteString,
-    IncompleteQuotation,
-    StackEmpty,
-    ExpectedBool,
-    ExpectedList,
-    ExpectedNumber,
-    ExpectedString,
-}
-
-#[derive(Debug, Clone)]
-enum Datatype {
-    Int(i128),
-    Float(f64),
-    Boolean(bool),
-    List(Vec<Datatype>),
-    String(String),
-    Code(String),
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use lab12::compile::compile;
+use lab12::{format_source, format_stack_item, Interpreter};
+use lab12::trace::Tracer;
+
+//Command-line flags, see `usage()`
+struct Options {
+    trace: bool,
+    profile: bool,
+    profile_out: String,
+    precision: Option<usize>,
+    search_path: Vec<PathBuf>,
+    //`lab12 fmt` formats the given files instead of running them
+    fmt: bool,
+    //`lab12 compile` translates the program to Rust, written to `output` or printed
+    compile: bool,
+    output: Option<String>,
+    files: Vec<String>,
 }
 
 fn main() {
-    println!("Welcome to the bprog interpreter!\nTesting or interpreting? (t/i)");
-    let input = read_line();
-    
-    if input.contains('t') {
-        tests();
-    } else {
-        loop { 
-            let result = interpreter(&read_line());
-            match result {
-                Ok(value) => println!("{}", format_stack_item(value)),
-                Err(e) => println!("Error: {:?}", e),
-            }
+    let options = match parse_args(std::env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, usage());
+            process::exit(2);
         }
-    }
-    
-}
-
-fn interpreter(line : &String) -> Result<Datatype, ProgramError> {
-    let mut tokens: Vec<_> = line.trim().split(' ').rev().collect();
-    
-    let mut stack : Vec<Datatype> = Vec::new();
-
-    while !tokens.is_empty() {
-        let token = tokens.pop().unwrap();
-
-        match datatype(token, &mut tokens) {
-            Some(value) => stack.push(value),
-            None => {
-                 //Checking the input for operators and function-calls, returns Some, if there are anything to be pushed back in the stack
-                let result : Option<Result<Datatype, ProgramError>> = match token {
-                    "+" => Some(add(stack.pop().unwrap(), stack.pop().unwrap())),
-                    "-" => Some(subtract(stack.pop().unwrap(), stack.pop().unwrap())),
-                    "*" => Some(multiply(stack.pop().unwrap(), stack.pop().unwrap())),
-                    "/" => Some(divide(stack.pop().unwrap(), stack.pop().unwrap())),
-                    "&&" => Some(and(stack.pop().unwrap(), stack.pop().unwrap())),
-                    "||" => Some(or(stack.pop().unwrap(), stack.pop().unwrap())),
-                    "not" => Some(not(stack.pop().unwrap())),
-                    "<" => Some(less_than(stack.pop().unwrap(), stack.pop().unwrap())),
-                    ">" => Some(larger_than(stack.pop().unwrap(), stack.pop().unwrap())),
-                    "div" => Some(div(stack.pop().unwrap(), stack.pop().unwrap())),
-                    "==" => Some(equal(stack.pop().unwrap(), stack.pop().unwrap())),
-                    "swap" => {
-                        let a = stack.pop().unwrap();
-                        let b = stack.pop().unwrap();
-                        stack.push(a);
-                        stack.push(b);
-                        None
-                    },
-                    "pop" => {
-                        stack.pop();
-                        None
-                    },
-                    "dup" => {
-                        let a = stack.pop().unwrap();
-                        stack.push(a.clone());
-                        stack.push(a);
-                        None
-                    },
-                    "length" => Some(length(stack.pop().unwrap())),
-                    "words" => Some(words(stack.pop().unwrap())),
-                    "parseInteger" => Some(parse_integer(stack.pop().unwrap())),
-                    "parseFloat" => Some(parse_float(stack.pop().unwrap())),
-                    "empty" => Some(empty(stack.pop().unwrap())),
-                    "head" => Some(head(stack.pop().unwrap())),
-                    "tail" => Some(tail(stack.pop().unwrap())),
-                    "cons" => Some(cons(stack.pop().unwrap(), stack.pop().unwrap())),
-                    "append" => Some(append(stack.pop().unwrap(), stack.pop().unwrap())),
-                    "exec" => Some(Ok(stack.pop().unwrap())),
-                    "map" => Some(map(stack.pop().unwrap(), &mut tokens)),
-                    "if" => Some(if_(stack.pop().unwrap(), &mut tokens)),
-                    "each" => Some(each(stack.pop().unwrap(), &mut tokens, &mut stack)),
-                    "foldl" => Some(foldl(stack.pop().unwrap(), stack.pop().unwrap(), &mut tokens)),
-                    "times" => Some(times(stack.pop().unwrap(), &mut tokens, &mut stack)),
-
-
-
-
-
-
-                    _ => Some(Err(ProgramError::InvalidOperation)),
-                };
-
-                match result {
-                    Some(Ok(value)) => stack.push(value),
-                    Some(Err(e)) => println!("Error: {:?}", e),
-                    None => (),
-                }
-            },
-        } 
-    }
-
-    //Handling the error case if the stack is empty
-    if stack.len() == 0 {
-        return Err(ProgramError::StackEmpty)
-    } else if stack.len() == 1 {
-        let last_element = stack.pop().unwrap();
-
-        //Evaluation of the last element in the stack if it is a code block, otherwise return the element
-        match last_element {
-            Datatype::Code(code) => {
-                match interpreter(&code) {
-                    Ok(value) => return Ok(value),
-                    Err(e) => return Err(e),
-                }
+    };
+    let mut interpreter = Interpreter::new();
+    interpreter.tracer = Tracer::new(options.trace, options.profile);
+    interpreter.search_path = options.search_path.clone();
+    interpreter.precision = options.precision;
+
+    let mut failed = false;
+    if options.fmt {
+        failed = !fmt(&options.files);
+    } else if options.compile {
+        failed = !compile_file(&options.files[0], options.output.as_deref());
+    } else if let Some(program) = options.files.first() {
+        match interpreter.run_file(program) {
+            Ok(value) => println!("{}", interpreter.format(value)),
+            Err(e) => {
+                println!("Error: {:?}", e);
+                failed = true;
             },
-            _ => return Ok(last_element)
         }
-        
     } else {
-        
-        //Final evaluation of the stack
-        let mut final_evaluation = String::new();
-        for item in stack {
-            final_evaluation.push_str(&format!("{} ",format_stack_item(item).as_str()));
-        }
-        //println!("Final evaluation: {}", final_evaluation);
-        return match interpreter(&final_evaluation) {
-            Ok(value) => Ok(value),
-            Err(e) => Err(e),
-        };
-    }
-    
-}
-
-fn foldl(init_accumulator : Datatype, list : Datatype, tokens : &mut Vec<&str>) -> Result<Datatype, ProgramError> {
-    
-    let token = tokens.pop().unwrap();
-    let operation = match datatype(token, tokens) {
-        Some(dt) => format_stack_item(dt),
-        None => token.to_string()
-    };
-
-    let iterable_list = match list {
-        Datatype::List(list) => list,
-        _ => return Err(ProgramError::ExpectedList),
-    };
-
-    let mut final_accumulation = match init_accumulator{
-        Datatype::Int(value) => Datatype::Int(value),
-        _ => return Err(ProgramError::ExpectedNumber),
-    };
-
-    for item in iterable_list {
-
-        //Does operation to the accumulated value with the list item as argument
-        let code_to_execute = format!("{} {} {}", format_stack_item(final_accumulation), format_stack_item(item), operation);
-        
-        match interpreter(&code_to_execute){
-            Ok(new_acc_value) => final_accumulation = new_acc_value,
-            Err(e) => return Err(e),
-        }
-    }
-
-    Ok(final_accumulation)
-}
-
-fn each(token : Datatype, tokens : &mut Vec<&str>, stack : &mut Vec<Datatype>) -> Result<Datatype, ProgramError> {
-    
-    let old_list = match token {
-        Datatype::List(list) => list,
-        _ => return Err(ProgramError::InvalidOperation),
-    };
-
-    //Call on map and get a list with elements
-    let new_list = match map(Datatype::List(old_list), tokens) {
-        Ok(Datatype::List(list)) => list,
-        _ => return Err(ProgramError::InvalidOperation),
-    };
+        println!("Welcome to the bprog interpreter!\nTesting or interpreting? (t/i)");
+        let input = read_line();
 
-    //Iterate over this list and push the element onto the stack 
-    for (i, item) in new_list.iter().enumerate() {
-        
-        if i != new_list.len() - 1 {
-            stack.push(item.clone());
+        if input.contains('t') {
+            tests(&mut interpreter);
         } else {
-            return Ok(item.clone())
+            repl(&mut interpreter);
         }
     }
 
-    return Err(ProgramError::InvalidOperation)
-    
-}
-
-fn if_(predicate : Datatype, tokens: &mut Vec<&str>) -> Result<Datatype, ProgramError>{
-    
-    let first_expression = tokens.pop().unwrap();
-
-    let true_expression = match datatype(first_expression, tokens){
-        Some(dt) => dt,
-        None => Datatype::Code(first_expression.to_string()),
-    };
-
-    let second_expression = tokens.pop().unwrap();
-
-    let false_expression = match datatype(second_expression, tokens){
-        Some(dt) => dt,
-        None => Datatype::Code(second_expression.to_string()),
-    };
-    // println!("True expression: {:?}", true_expression);
-    // println!("False expression: {:?}", false_expression);
-
-    let expression : Datatype;
-    
-    match predicate {
-        Datatype::Boolean(boolean) => {
-            if boolean {
-                expression = true_expression;
-            } else {
-                expression = false_expression;
-            }
-
-            Ok(expression)
-        },
-        _ => Err(ProgramError::ExpectedBool)
-    }
-}
-
-fn map(list: Datatype, tokens: &mut Vec<&str>) -> Result<Datatype, ProgramError> {
-    let operation = tokens.pop().ok_or(ProgramError::InvalidOperation)?;
-    let code_block = match datatype(operation, tokens) {
-        Some(Datatype::Code(code)) => code,
-        _ => operation.to_string(),
-    };
-
-    //println!("Code block in map: {}", code_block);
-
-    let new_list = match list {
-        Datatype::List(list) => {
-            let mut new_list = Vec::new();
-            for item in list {
-                let formatted_item = format_stack_item(item);
-                let formatted_code = format!("{} {}", formatted_item, &code_block);
-                //println!("Formatted code: {}", formatted_code);
-                let result = interpreter(&formatted_code)?;
-                match result {
-                    Datatype::Code(code_exp) => {
-                        let code_to_execute = format!("{} {}", formatted_item, code_exp);
-                        new_list.push(interpreter(&code_to_execute)?);
-                    }
-                    _ => new_list.push(result),
-                }
-            }
-            Datatype::List(new_list)
+    if options.profile {
+        eprint!("{}", interpreter.tracer.report());
+        match fs::write(&options.profile_out, interpreter.tracer.chrome_trace()) {
+            Ok(_) => eprintln!("Chrome trace written to {}", options.profile_out),
+            Err(e) => eprintln!("Could not write {}: {}", options.profile_out, e),
         }
-        _ => return Err(ProgramError::ExpectedList),
-    };
-
-    Ok(new_list)
-}
-
-fn read_line() -> String {
-    let mut input = String::new();
-    match io::stdin().read_line(&mut input) {
-        Ok(_) => input,
-        Err(error) => panic!("Error: {}", error), 
     }
-}
 
-fn datatype(token: &str, tokens: &mut Vec<&str>) -> Option<Datatype> {
-    match token {
-        "{" => {
-            match code(tokens) {
-                Ok(value) => Some(value),
-                Err(_) => None,
-            }
-        }
-        "[" => {
-            match list(token, tokens) {
-                Ok(value) => Some(value),
-                Err(_) => None,
-            }
-        }
-        _ if token.parse::<f64>().is_ok() && token.contains('.') => {
-            Some(Datatype::Float(token.parse::<f64>().unwrap()))
-        }
-        _ if token.parse::<i128>().is_ok() => {
-            Some(Datatype::Int(token.parse::<i128>().unwrap()))
-        }
-        "False" | "True" => {
-            let bool_value = token.to_ascii_lowercase();
-            Some(Datatype::Boolean(bool_value.parse::<bool>().unwrap()))
-        }
-        "\"" => {
-            match string(tokens) {
-                Ok(value) => Some(value),
-                Err(_) => None,
-            }
-        }
-        _ => None,
+    if failed {
+        process::exit(1);
     }
 }
 
-
-fn list(token: &str, tokens: &mut Vec<&str>) -> Result<Datatype, ProgramError> {
-    let mut list_ : Vec<Datatype> = Vec::new();
-
-    //Looping over all the list elements till the closing bracket
-    while token != "]" {
-
-        //If the token is a list, call the list function recursively
-        let new_token = tokens.pop().unwrap();
-        if new_token == "]" {
-            return Ok(Datatype::List(list_));
-        }
-        match datatype(new_token, tokens) {
-            Some(value) => list_.push(value),
-            None => return Err(ProgramError::IncompleteList),
+//Reads one line at a time, the stack and the defined functions are kept between lines.
+//`:save file` writes them to a snapshot file and `:restore file` reads them back
+fn repl(interpreter : &mut Interpreter) {
+    loop {
+        let line = read_line();
+        //End of input, e.g. a piped program or Ctrl-D
+        if line.is_empty() {
+            break;
         }
-    }
-    Ok(Datatype::List(list_))
-}
-
-fn string(tokens: &mut Vec<&str>) -> Result<Datatype, ProgramError> {
-    let mut string_ = String::new();
-    let mut token = tokens.pop().unwrap();
-    while token != "\"" {
-        string_.push_str(format!(" {}", token).as_str());
-        if tokens.is_empty() {
-            return Err(ProgramError::IncompleteString)
+        if let Some(file) = line.trim().strip_prefix(":save ") {
+            match fs::write(file.trim(), interpreter.snapshot()) {
+                Ok(_) => println!("Saved to {}", file.trim()),
+                Err(e) => println!("Error: {}", e),
+            }
+            continue;
         }
-        token = tokens.pop().unwrap();
-    }
-    Ok(Datatype::String(string_.trim().to_string()))
-}
-
-fn code(tokens: &mut Vec<&str>) -> Result<Datatype, ProgramError> {
-    let mut code_ = String::new();
-    let mut new_token = tokens.pop().unwrap();
-    while new_token != "}" {
-        
-        if new_token == "{" {
-            match code(tokens) {
-                Ok(value) => {
-                    let inner_code = format_stack_item(value);
-                    code_.push_str(format!(" {{ {} }}", inner_code).as_str());
+        if let Some(file) = line.trim().strip_prefix(":restore ") {
+            match fs::read_to_string(file.trim()).map_err(|e| e.to_string()).and_then(|snapshot| interpreter.restore(&snapshot).map_err(|e| format!("{:?}", e))) {
+                Ok(_) => {
+                    let items : Vec<String> = interpreter.stack.iter().map(|item| interpreter.format(item.clone())).collect();
+                    println!("{}", items.join(" "));
                 },
-                Err(e) => return Err(e)
+                Err(e) => println!("Error: {}", e),
             }
+            continue;
         }
-
-        if new_token == "}" {
-            return Ok(Datatype::Code(code_));
-        }
-
-        if new_token != "{" {
-            code_.push_str(format!(" {}", new_token).as_str());
-        }
-
-        if tokens.is_empty() {
-            return Err(ProgramError::IncompleteQuotation)
+        match interpreter.execute(&line) {
+            Ok(_) => {
+                let items : Vec<String> = interpreter.stack.iter().map(|item| interpreter.format(item.clone())).collect();
+                println!("{}", items.join(" "));
+            },
+            Err(e) => println!("Error: {:?}", e),
         }
-        new_token = tokens.pop().unwrap();
     }
-    //println!("Code: {}", code_.trim().to_string());
-    Ok(Datatype::Code(code_.trim().to_string()))
-    
-
 }
 
-fn empty(a : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match a {
-        Datatype::List(list) => Ok(Datatype::Boolean(list.is_empty())),
-        _ => Err(ProgramError::InvalidOperation),
-    };
-    result
-}
-
-fn append(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::List(mut list), Datatype::List(mut list2)) => {
-            list2.append(&mut list);
-            Ok(Datatype::List(list2))
-        },
-        _ => Err(ProgramError::InvalidOperation),
-    };
-    result
-}
-
-fn cons(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::List(mut list), item) => {
-            list.insert(0, item);
-            Ok(Datatype::List(list))
-        },
-        _ => Err(ProgramError::InvalidOperation),
-    };
-    result
-}
-
-fn tail(a : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match a {
-        Datatype::List(list) => {
-            if list.is_empty() {
-                Err(ProgramError::InvalidOperation)
-            } else {
-                let mut new_list = list.clone();
-                new_list.remove(0);
-                Ok(Datatype::List(new_list))
-            }
-        },
-        _ => Err(ProgramError::InvalidOperation),
-    };
-    result
-}
-
-fn head(a : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match a {
-        Datatype::List(list) => {
-            if list.is_empty() {
-                Err(ProgramError::InvalidOperation)
-            } else {
-                Ok(list[0].clone())
-            }
-        },
-        _ => Err(ProgramError::InvalidOperation),
-    };
-    result
-}
-
-fn length(a : Datatype) -> Result<Datatype, ProgramError> {
-    match a {
-        Datatype::List(list) => Ok(Datatype::Int(list.len() as i128)),
-        Datatype::String(string) => Ok(Datatype::Int(string.len() as i128)),
-        Datatype::Code(string) => Ok(Datatype::Int(string.split(' ').collect::<Vec<&str>>().len() as i128)),
-        _ => Err(ProgramError::InvalidOperation),
-    }
-}
-
-fn parse_integer(a : Datatype) -> Result<Datatype, ProgramError> {
-    match a {
-        Datatype::String(value) => {
-            match value.parse::<i128>() {
-                Ok(value) => Ok(Datatype::Int(value)),
-                Err(_) => Err(ProgramError::InvalidOperation),
-            }
-        },
-        _ => Err(ProgramError::InvalidOperation),
+//Formats the files in place, or standard input to standard output when no files are given
+fn fmt(files : &[String]) -> bool {
+    if files.is_empty() {
+        let source = io::read_to_string(io::stdin()).unwrap_or_default();
+        return match format_source(&source) {
+            Ok(formatted) => {
+                print!("{}", formatted);
+                true
+            },
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                false
+            },
+        };
     }
-}
 
-fn parse_float(a : Datatype) -> Result<Datatype, ProgramError> {
-    match a {
-        Datatype::String(value) => {
-            match value.parse::<f64>() {
-                Ok(value) => Ok(Datatype::Float(value)),
-                Err(_) => Err(ProgramError::InvalidOperation),
-            }
-        },
-        _ => Err(ProgramError::InvalidOperation),
+    let mut ok = true;
+    for file in files {
+        let result = fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|source| format_source(&source).map_err(|e| format!("{:?}", e)))
+            .and_then(|formatted| fs::write(file, formatted).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            eprintln!("{}: Error: {}", file, e);
+            ok = false;
+        }
     }
+    ok
 }
 
-fn words(a : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match a {
-        Datatype::String(value) => {
-            let words : Vec<Datatype> = value.split(' ').map(|x| Datatype::String(x.to_string())).collect();
-            Ok(Datatype::List(words))
+fn compile_file(file : &str, output : Option<&str>) -> bool {
+    let path = match fs::canonicalize(file) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("{}: Error: {}", file, e);
+            return false;
         },
-        _ => Err(ProgramError::ExpectedString),
     };
-    result
-}
-
-
-fn div(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::Int(a), Datatype::Int(b)) => Ok(Datatype::Int(b / a)),
-        (Datatype::Float(a), Datatype::Float(b)) => Ok(Datatype::Int((b as i128) / (a as i128))),
-        (Datatype::Int(a), Datatype::Float(b)) => Ok(Datatype::Int((b as i128) / a as i128)),
-        (Datatype::Float(a), Datatype::Int(b)) => Ok(Datatype::Int((b as i128) / (a as i128))),
-        _ => Err(ProgramError::ExpectedNumber),
-    };
-    result
-}
-
-fn equal(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::Int(a), Datatype::Int(b)) => Ok(Datatype::Boolean(b == a)),
-        (Datatype::Float(a), Datatype::Float(b)) => Ok(Datatype::Boolean(b == a)),
-        (Datatype::Int(a), Datatype::Float(b)) => Ok(Datatype::Boolean(b == a as f64)),
-        (Datatype::Float(a), Datatype::Int(b)) => Ok(Datatype::Boolean((b as f64) == a)),
-        (Datatype::Boolean(a), Datatype::Boolean(b)) => Ok(Datatype::Boolean(b == a)),
-        (Datatype::String(a), Datatype::String(b)) => Ok(Datatype::Boolean(b == a)),
-        (Datatype::List(a), Datatype::List(b)) => Ok(Datatype::Boolean(equal_list(a, b))),
-        _ => Err(ProgramError::InvalidOperation),
-    };
-    result
-}
-
-fn equal_list(a : Vec<Datatype>, b : Vec<Datatype>) -> bool {
-    if a.len() != b.len() {
+    let result = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|source| compile(&source, path.parent()).map_err(|e| format!("{:?}", e)))
+        .and_then(|rust| match output {
+            Some(output) => fs::write(output, rust).map_err(|e| e.to_string()),
+            None => {
+                print!("{}", rust);
+                Ok(())
+            },
+        });
+    if let Err(e) = result {
+        eprintln!("{}: Error: {}", file, e);
         return false;
     }
-
-    let mut valid : bool = true;
-    for (i, item) in a.iter().enumerate() {
-        if let Datatype::List(f) = item {
-            if let Datatype::List(g) = &b[i] {
-                if !equal_list(f.clone(), g.clone()) {
-                    return false;
+    true
+}
+
+fn usage() -> &'static str {
+    "usage: lab12 [--trace] [--profile] [--profile-out <file>] [--precision <n>] [-I <dir>]... [program.bprog]\n\
+    \x20      lab12 fmt [file.bprog]...\n\
+    \x20      lab12 compile program.bprog [-o program.rs]\n\
+    \x20 --trace               log every executed word with the stack before and after\n\
+    \x20 --profile             report call counts and cumulative time per word on exit\n\
+    \x20 --profile-out <file>  where to write the Chrome trace-event JSON (default bprog-profile.json)\n\
+    \x20 --precision <n>       print floats with n decimals\n\
+    \x20 -I, --path <dir>      add a directory to the search path for `import`\n\
+    \x20 -o, --output <file>   where `compile` writes the Rust source (default standard output)"
+}
+
+fn parse_args(args : Vec<String>) -> Result<Options, String> {
+    let mut options = Options {
+        trace: false,
+        profile: false,
+        profile_out: "bprog-profile.json".to_string(),
+        precision: None,
+        search_path: Vec::new(),
+        fmt: false,
+        compile: false,
+        output: None,
+        files: Vec::new(),
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => options.trace = true,
+            "--profile" => options.profile = true,
+            "--profile-out" => {
+                options.profile_out = args.next().ok_or("--profile-out expects a file name")?;
+                options.profile = true;
+            },
+            "--precision" => {
+                let digits = args.next().ok_or("--precision expects a number of decimals")?;
+                match digits.parse() {
+                    Ok(digits) if digits > 0 => options.precision = Some(digits),
+                    _ => return Err(format!("--precision expects a positive number of decimals, not {}", digits)),
                 }
-            } else {
-                return false;
-            }
-        } else if let Ok(e) = equal(item.clone(), b[i].clone()) {
-            if let Datatype::Boolean(c) = e {
-                valid = valid && c;
-            } 
+            },
+            "-I" | "--path" => options.search_path.push(PathBuf::from(args.next().ok_or("--path expects a directory")?)),
+            "-o" | "--output" => options.output = Some(args.next().ok_or("--output expects a file name")?),
+            "fmt" if !options.fmt && !options.compile && options.files.is_empty() => options.fmt = true,
+            "compile" if !options.fmt && !options.compile && options.files.is_empty() => options.compile = true,
+            _ if arg.starts_with('-') => return Err(format!("Unknown argument: {}", arg)),
+            _ => options.files.push(arg),
         }
     }
-    return true
-}
-
-fn larger_than(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::Int(a), Datatype::Int(b)) => Ok(Datatype::Boolean(b > a)),
-        (Datatype::Float(a), Datatype::Float(b)) => Ok(Datatype::Boolean(b > a)),
-        (Datatype::Int(a), Datatype::Float(b)) => Ok(Datatype::Boolean(b > a as f64)),
-        (Datatype::Float(a), Datatype::Int(b)) => Ok(Datatype::Boolean((b as f64) > a)),
-        _ => Err(ProgramError::InvalidOperation),
-    };
-    result
-}
-
-fn less_than(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::Int(a), Datatype::Int(b)) => Ok(Datatype::Boolean(b < a)),
-        (Datatype::Float(a), Datatype::Float(b)) => Ok(Datatype::Boolean(b < a)),
-        (Datatype::Int(a), Datatype::Float(b)) => Ok(Datatype::Boolean(b < a as f64)),
-        (Datatype::Float(a), Datatype::Int(b)) => Ok(Datatype::Boolean((b as f64) < a)),
-        _ => Err(ProgramError::ExpectedNumber),
-    };
-    result
-}
-
-
-
-fn and(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::Boolean(a), Datatype::Boolean(b)) => Ok(Datatype::Boolean(a && b)),
-        _ => Err(ProgramError::ExpectedBool),
-    };
-    result
-}
-
-fn or(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::Boolean(a), Datatype::Boolean(b)) => Ok(Datatype::Boolean(a || b)),
-        _ => Err(ProgramError::ExpectedBool),
-    };
-    result
-}
-
-fn not(a : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match a {
-        Datatype::Boolean(a) => Ok(Datatype::Boolean(!a)),
-        _ => Err(ProgramError::InvalidOperation),
-    };
-    result
-}
-
-fn add(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::Int(a), Datatype::Int(b)) => Ok(Datatype::Int(a + b)),
-        (Datatype::Float(a), Datatype::Float(b)) => Ok(Datatype::Float(a + b)),
-        (Datatype::Int(a), Datatype::Float(b)) => Ok(Datatype::Float(a as f64 + b)),
-        (Datatype::Float(a), Datatype::Int(b)) => Ok(Datatype::Float(a + b as f64)),
-        _ => Err(ProgramError::ExpectedNumber),
-    };
-    result
-}
-
-fn subtract(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::Int(a), Datatype::Int(b)) => Ok(Datatype::Int(b - a)),
-        (Datatype::Float(a), Datatype::Float(b)) => Ok(Datatype::Float(b - a)),
-        (Datatype::Float(a), Datatype::Int(b)) => Ok(Datatype::Float(b as f64 - a)),
-        (Datatype::Int(a), Datatype::Float(b)) => Ok(Datatype::Float(b - a as f64)),
-        _ => Err(ProgramError::ExpectedNumber),
-    };
-    result
-}
-
-fn times(number : Datatype, tokens : &mut Vec<&str>, stack : &mut Vec<Datatype>) -> Result<Datatype, ProgramError> {
-    let token = tokens.pop().unwrap();
-    let evaluated_codeblock = match datatype(token, tokens) {
-        Some(Datatype::Code(code)) => match interpreter(&code) {
-            Ok(dt) => dt,
-            Err(e) => return Err(e),
-        },
-        Some(dt) => dt,
-        None => Datatype::Code(token.to_string()),
-    };
-
-    match number {
-        Datatype::Int(value) => {
-            let new_list = Vec::new();
-            for i in 0..value {
-                if i < value - 1 {
-                    stack.push(evaluated_codeblock.clone());
-                } else {
-                    return Ok(evaluated_codeblock.clone());
-                }
-            }
-            Ok(Datatype::List(new_list))
-        },
-        _ => Err(ProgramError::InvalidOperation),
+    if !options.fmt && options.files.len() > 1 {
+        return Err("Only one program can be run at a time".to_string());
     }
-}
-
-fn multiply(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::Int(a), Datatype::Int(b)) => Ok(Datatype::Int(a * b)),
-        (Datatype::Float(a), Datatype::Float(b)) => Ok(Datatype::Float(a * b)),
-        (Datatype::Int(a), Datatype::Float(b)) => Ok(Datatype::Float(a as f64 * b)),
-        (Datatype::Float(a), Datatype::Int(b)) => Ok(Datatype::Float(a * b as f64)),
-        _ => Err(ProgramError::InvalidOperation),
-    };
-    result
-}
-
-fn divide(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
-    let result = match (a, b) {
-        (Datatype::Int(a), Datatype::Int(b)) => Ok(Datatype::Float((b as f64) / (a as f64))),
-        (Datatype::Float(a), Datatype::Float(b)) => Ok(Datatype::Float(b / a)),
-        (Datatype::Int(a), Datatype::Float(b)) => Ok(Datatype::Float(b / a as f64)),
-        (Datatype::Float(a), Datatype::Int(b)) => Ok(Datatype::Float(b as f64 / a)),
-        _ => Err(ProgramError::InvalidOperation),
-    };
-    result
-}
-
-
-fn format_stack_item(stack_item : Datatype) -> String {
-    match stack_item {
-        Datatype::Int(value) => format!("{}", value),
-        Datatype::Float(value) => format!("{:?}", value),
-        Datatype::Boolean(value) => format!("{}", if value { "True" } else { "False" }),
-        Datatype::List(list) => format!("[{}]", format_list(&list)),
-        Datatype::String(value) => format!("\" {} \"", value),
-        Datatype::Code(value) => format!("{}", value),
+    if options.compile && options.files.is_empty() {
+        return Err("compile expects a program".to_string());
     }
+    Ok(options)
 }
-
-fn format_list(list : &Vec<Datatype>) -> String {
-    let mut list_str = String::new();
-    let length = list.len();
-    for (i, item) in list.iter().enumerate() {
-        if let Datatype::List(f) = item {
-            write!(list_str, "[{}]", format_list(f)).unwrap();
-        } else {
-            write!(list_str, "{}", format_stack_item(item.clone())).unwrap();
-        }
-        if i < length - 1 {
-            write!(list_str, ",").unwrap();
-        }
-    }
-    if list_str == "" {
-        return " ".to_string()
-    } else {
-        return list_str
+fn read_line() -> String {
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(_) => input,
+        Err(error) => panic!("Error: {}", error), 
     }
 }
-
-fn tests() {
+fn tests(interpreter : &mut Interpreter) {
     let testings: Vec<(String, String)> = vec![
         ("3".to_string(), "3".to_string()),
         ("121231324135634563456363567".to_string(), "121231324135634563456363567".to_string()),
@@ -699,19 +236,22 @@ fn tests() {
         ("-1.1".to_string(), "-1.1".to_string()),
         ("False".to_string(), "False".to_string()),
         ("True".to_string(), "True".to_string()),
-        ("[ [ ] [ ] ]".to_string(), "[[ ],[ ]]".to_string()),
-        ("[ False [ ] True [ 1 2 ] ]".to_string(), "[False,[ ],True,[1,2]]".to_string()),
+        ("[ [ ] [ ] ]".to_string(), "[ [ ] [ ] ]".to_string()),
+        ("[ False [ ] True [ 1 2 ] ]".to_string(), "[ False [ ] True [ 1 2 ] ]".to_string()),
         ("\" [ so { not if ] and } \"".to_string(), "\" [ so { not if ] and } \"".to_string()),
-        //("{ 20 10 + }".to_string(), "{ 20 10 + }".to_string()),
-        //("[ { + } { 10 + } { 20 10 + } ]".to_string(), "[{ + },{ 10 + },{ 20 10 + }]".to_string()),
+        ("{ 20 10 + }".to_string(), "{ 20 10 + }".to_string()),
+        ("[ { + } { 10 + } { 20 10 + } ]".to_string(), "[ { + } { 10 + } { 20 10 + } ]".to_string()),
+        ("{ { } [ ] }".to_string(), "{ { } [ ] }".to_string()),
+        ("0.0000001".to_string(), "1.0e-7".to_string()),
+        ("100000000000000000000.0".to_string(), "1.0e20".to_string()),
         ("1 1 +".to_string(), "2".to_string()),
         ("10 20 *".to_string(), "200".to_string()),
         ("20 2 div".to_string(), "10".to_string()),
         ("20 2 /".to_string(), "10.0".to_string()),
         ("1 1.0 +".to_string(), "2.0".to_string()),
         ("10 20.0 *".to_string(), "200.0".to_string()),
-        ("20 2.0 div".to_string(), "10".to_string()),
-        ("20.0 2.0 div".to_string(), "10".to_string()),
+        ("20 2.0 div".to_string(), "10.0".to_string()),
+        ("20.0 2.0 div".to_string(), "10.0".to_string()),
         ("False False &&".to_string(), "False".to_string()),
         ("False True ||".to_string(), "True".to_string()),
         ("False not".to_string(), "True".to_string()),
@@ -729,10 +269,157 @@ fn tests() {
     ("[ 1 2 ] [ 1 2 ] ==".to_string(), "True".to_string()),
     ("[ [ ] ] [ [ ] ] ==".to_string(), "True".to_string()),
 
+    // Math
+    ("7 2 div".to_string(), "3".to_string()),
+    ("-7 2 div".to_string(), "-4".to_string()),
+    ("7.5 2 div".to_string(), "3.0".to_string()),
+    ("2 10 pow".to_string(), "1024".to_string()),
+    ("2 -1 pow".to_string(), "0.5".to_string()),
+    ("2.0 3 pow".to_string(), "8.0".to_string()),
+    ("10 200 pow".to_string(), "1.0e200".to_string()),
+    ("16 sqrt".to_string(), "4.0".to_string()),
+    ("-3 abs".to_string(), "3".to_string()),
+    ("-2.5 abs".to_string(), "2.5".to_string()),
+    ("2.7 floor".to_string(), "2".to_string()),
+    ("-2.7 floor".to_string(), "-3".to_string()),
+    ("2.1 ceil".to_string(), "3".to_string()),
+    ("2.5 round".to_string(), "3".to_string()),
+    ("-2.5 round".to_string(), "-3".to_string()),
+    ("5 floor".to_string(), "5".to_string()),
+    ("-2.7 toInt".to_string(), "-2".to_string()),
+    ("3 toFloat".to_string(), "3.0".to_string()),
+    ("3 2.5 min".to_string(), "2.5".to_string()),
+    ("3 2.5 max".to_string(), "3".to_string()),
+    ("1 1.0 max".to_string(), "1".to_string()),
+    ("1.0e300 1.0e300 * floor".to_string(), "InvalidOperation".to_string()),
+    ("\" a \" 1 max".to_string(), "ExpectedNumber".to_string()),
+    ("True 1 *".to_string(), "ExpectedNumber".to_string()),
+    ("2 precision 1 3 /".to_string(), "0.33".to_string()),
+    ("1 precision [ 2.25 { 1 } ]".to_string(), "[ 2.2 { 1 } ]".to_string()),
+    ("0 precision 2.5".to_string(), "InvalidOperation".to_string()),
+    ("-1 precision 2.5".to_string(), "InvalidOperation".to_string()),
+
+    // Rationals
+    ("1/3".to_string(), "1/3".to_string()),
+    ("2/6".to_string(), "1/3".to_string()),
+    ("-2/4".to_string(), "-1/2".to_string()),
+    ("4/2".to_string(), "2".to_string()),
+    ("1/3 1/6 +".to_string(), "1/2".to_string()),
+    ("1/2 1/2 +".to_string(), "1".to_string()),
+    ("1/3 1 -".to_string(), "-2/3".to_string()),
+    ("2/3 3 *".to_string(), "2".to_string()),
+    ("1/3 2 /".to_string(), "1/6".to_string()),
+    ("1 1/3 /".to_string(), "3".to_string()),
+    ("1/2 0.25 +".to_string(), "0.75".to_string()),
+    ("1 3 /".to_string(), "0.3333333333333333".to_string()),
+    ("1/3 0 /".to_string(), "DivisionByZero".to_string()),
+    ("7/2 1/3 div".to_string(), "10".to_string()),
+    ("2/3 2 pow".to_string(), "4/9".to_string()),
+    ("2/3 -2 pow".to_string(), "9/4".to_string()),
+    ("-7/2 floor".to_string(), "-4".to_string()),
+    ("-7/2 ceil".to_string(), "-3".to_string()),
+    ("-7/2 round".to_string(), "-4".to_string()),
+    ("7/3 round".to_string(), "2".to_string()),
+    ("-7/2 toInt".to_string(), "-3".to_string()),
+    ("-1/2 abs".to_string(), "1/2".to_string()),
+    ("1/4 toFloat".to_string(), "0.25".to_string()),
+    ("6/4 numerator 6/4 denominator [ ] cons cons".to_string(), "[ 3 2 ]".to_string()),
+    ("5 denominator".to_string(), "1".to_string()),
+    ("0.5 numerator".to_string(), "InvalidOperation".to_string()),
+    ("1/3 1/4 >".to_string(), "True".to_string()),
+    ("1/2 0.5 ==".to_string(), "True".to_string()),
+    ("1/3 0.3333333333333333 ==".to_string(), "False".to_string()),
+    ("1/3 0.3333333333333333 >".to_string(), "True".to_string()),
+    ("-1/3 -0.3333333333333333 <".to_string(), "True".to_string()),
+    ("18014398509481985/2 9007199254740992.0 ==".to_string(), "False".to_string()),
+    ("18014398509481985/2 9007199254740992.0 >".to_string(), "True".to_string()),
+    ("[ 9007199254740993 18014398509481985/2 9007199254740992.0 9007199254740992 18014398509481983/2 ] sort".to_string(), "[ 18014398509481983/2 9007199254740992.0 9007199254740992 18014398509481985/2 9007199254740993 ]".to_string()),
+    ("[ 18014398509481985/2 9007199254740992.0 9007199254740992 ] dedup".to_string(), "[ 18014398509481985/2 9007199254740992.0 ]".to_string()),
+    ("[ 9007199254740992 9007199254740992.0 18014398509481985/2 ] dedup".to_string(), "[ 9007199254740992 18014398509481985/2 ]".to_string()),
+    ("[ 1/2 1/3 2/3 0 ] sort".to_string(), "[ 0 1/3 1/2 2/3 ]".to_string()),
+    ("1/0".to_string(), "DivisionByZero".to_string()),
+    ("170141183460469231731687303715884105727/2 2 *".to_string(), "1.7014118346046923e38".to_string()),
+    ("1/3 case { rational { pop True } _ { pop False } }".to_string(), "True".to_string()),
+
+    // Malformed input and edge cases
+    ("[ 1 2".to_string(), "IncompleteList".to_string()),
+    ("{ 1".to_string(), "IncompleteQuotation".to_string()),
+    ("\" abc".to_string(), "IncompleteString".to_string()),
+    ("[ foo { 1 } ]".to_string(), "[ 'foo { 1 } ]".to_string()),
+    ("+".to_string(), "StackEmpty".to_string()),
+    ("pop".to_string(), "StackEmpty".to_string()),
+    ("1 0 div".to_string(), "DivisionByZero".to_string()),
+    ("1 0 /".to_string(), "inf".to_string()),
+    ("-1.0 0 /".to_string(), "-inf".to_string()),
+    ("0.0 0.0 / dup ==".to_string(), "True".to_string()),
+    ("170141183460469231731687303715884105727 1 +".to_string(), "1.7014118346046923e38".to_string()),
+    ("-170141183460469231731687303715884105728 -1 div".to_string(), "1.7014118346046923e38".to_string()),
+    ("[ 170141183460469231731687303715884105727 1.7014118346046923e38 170141183460469231731687303715884105726 ] sort".to_string(), "[ 170141183460469231731687303715884105726 170141183460469231731687303715884105727 1.7014118346046923e38 ]".to_string()),
+
+    // Comparisons
+    ("[ 1 2 ] [ 1 3 ] ==".to_string(), "False".to_string()),
+    ("[ 1 [ 2 ] ] [ 1 [ 2.0 ] ] ==".to_string(), "True".to_string()),
+    ("{ 1 + } { 1 + } ==".to_string(), "True".to_string()),
+    ("1 \" 1 \" ==".to_string(), "False".to_string()),
+    ("'a 'a ==".to_string(), "True".to_string()),
+    ("1 2 !=".to_string(), "True".to_string()),
+    ("\" abc \" \" abd \" <".to_string(), "True".to_string()),
+    ("[ 1 2 ] [ 1 2 3 ] <".to_string(), "True".to_string()),
+    ("[ 2 ] [ 1 5 ] >".to_string(), "True".to_string()),
+    ("10 10.0 <=".to_string(), "True".to_string()),
+    ("9 10 >=".to_string(), "False".to_string()),
+    ("1 \" 1 \" <".to_string(), "InvalidOperation".to_string()),
+    ("[ 3 1 2 ] sort".to_string(), "[ 1 2 3 ]".to_string()),
+    ("[ \" b \" \" a \" ] sort".to_string(), "[ \" a \" \" b \" ]".to_string()),
+    ("[ [ ] \" a \" 2 1.5 True ] sort".to_string(), "[ True 1.5 2 \" a \" [ ] ]".to_string()),
+    ("[ 1 2 1 3 2 ] dedup".to_string(), "[ 1 2 3 ]".to_string()),
+    ("[ 1 1.0 ] dedup".to_string(), "[ 1 ]".to_string()),
+    ("1 sort".to_string(), "ExpectedList".to_string()),
+
     // Stack operations
     ("10 20 swap pop".to_string(), "20".to_string()),
     ("10 dup dup + swap pop".to_string(), "20".to_string()),
     ("10 20 swap dup + div".to_string(), "1".to_string()),
+    ("1 2 over [ ] cons cons cons".to_string(), "[ 1 2 1 ]".to_string()),
+    ("1 2 3 rot [ ] cons cons cons".to_string(), "[ 2 3 1 ]".to_string()),
+    ("1 2 nip".to_string(), "2".to_string()),
+    ("1 2 tuck [ ] cons cons cons".to_string(), "[ 2 1 2 ]".to_string()),
+    ("1 2 2dup [ ] cons cons cons cons".to_string(), "[ 1 2 1 2 ]".to_string()),
+    ("1 2 3 depth".to_string(), "ProgramFinishedWithMultipleValues".to_string()),
+    ("5 6 depth nip nip".to_string(), "2".to_string()),
+    ("1 2 3 clear depth".to_string(), "0".to_string()),
+    ("1 over".to_string(), "StackEmpty".to_string()),
+
+    // Local bindings
+    ("3 4 { | a b | a b * a + } exec".to_string(), "15".to_string()),
+    ("10 4 { | a b | a b - } exec".to_string(), "6".to_string()),
+    ("2 [ 1 2 3 ] { | n list | list map { n * } } exec".to_string(), "[ 2 4 6 ]".to_string()),
+    ("2 { | a | 10 { | a | a } exec a * } exec".to_string(), "20".to_string()),
+    ("2 { | a | 10 { | b | a b + } exec } exec".to_string(), "12".to_string()),
+    ("area { | w h | w h * } fun 3 4 area".to_string(), "12".to_string()),
+    ("x { | a | a } fun 5 { | a | 1 x } exec".to_string(), "1".to_string()),
+    ("y { a } fun 5 { | a | y } exec".to_string(), "UnknownWord".to_string()),
+    ("1 { | dup | dup dup + } exec".to_string(), "2".to_string()),
+    ("{ | a b | a } exec".to_string(), "StackEmpty".to_string()),
+    ("1 { | a } exec".to_string(), "IncompleteBinding".to_string()),
+
+    // Closures
+    ("adder { | n | { n + } } fun 10 5 adder exec".to_string(), "15".to_string()),
+    ("adder { | n | { n + } } fun 5 adder".to_string(), "( 5 { | n | { n + } } exec )".to_string()),
+    ("adder { | n | { n + } } fun 5 adder [ ] cons".to_string(), "[ ( 5 { | n | { n + } } exec ) ]".to_string()),
+    ("2 [ 1 ] { | a b | { b a } } exec".to_string(), "( 2 [ 1 ] { | a b | { b a } } exec )".to_string()),
+    ("1 [ ( 5 { | n | { n + } } exec ) ] each exec".to_string(), "6".to_string()),
+    ("( 1 2 { | n | { n } } exec )".to_string(), "InvalidOperation".to_string()),
+    ("adder { | n | { n + } } fun 3 adder 4 adder 1 swap exec swap exec".to_string(), "8".to_string()),
+    ("apply { | f | 100 f exec } fun 1 { | f | { f + } } exec apply".to_string(), "101".to_string()),
+    ("scale { | k | [ 1 2 3 ] map { k * } } fun 10 scale".to_string(), "[ 10 20 30 ]".to_string()),
+    ("3 { | k | 1 iterate { k * } } exec 4 take toList".to_string(), "[ 1 3 9 27 ]".to_string()),
+    ("3 { | k | 1 iterate { k * } } exec 4 take".to_string(), "( 1 iterate ( 3 { | k | { k * } } exec ) 4 take )".to_string()),
+    ("2 { | k | { { k } } } exec exec exec".to_string(), "2".to_string()),
+    ("1 { | a | 2 { | b | { a b + } } exec } exec exec".to_string(), "3".to_string()),
+    ("1 { | n | { n } } exec 2 { | n | { n } } exec ==".to_string(), "False".to_string()),
+    ("[ 1 2 ] case { [ h | t ] { { h } } } exec".to_string(), "1".to_string()),
+    ("5 { n } { | n q | q exec } exec".to_string(), "UnknownWord".to_string()),
 
     // Length
     ("\" hello \" length".to_string(), "5".to_string()),
@@ -743,21 +430,84 @@ fn tests() {
     // String parsing
     ("\" 12 \" parseInteger".to_string(), "12".to_string()),
     ("\" 12.34 \" parseFloat".to_string(), "12.34".to_string()),
-    ("\" adam bob charlie \" words".to_string(), "[\" adam \",\" bob \",\" charlie \"]".to_string()),
+    ("\" adam bob charlie \" splitWords".to_string(), "[ \" adam \" \" bob \" \" charlie \" ]".to_string()),
+
+    // String literals
+    ("\" a  b \"".to_string(), "\" a  b \"".to_string()),
+    ("\"  a \" length".to_string(), "2".to_string()),
+    ("\"\" length".to_string(), "0".to_string()),
+    ("\" \" length".to_string(), "0".to_string()),
+    ("\"abc\"".to_string(), "\" abc \"".to_string()),
+    ("\" say \\\"hi\\\" \"".to_string(), "\" say \\\"hi\\\" \"".to_string()),
+    ("\" say \\\"hi\\\" \" length".to_string(), "8".to_string()),
+    ("\" a\\nb\\tc \" length".to_string(), "5".to_string()),
+    ("\" a\\nb \"".to_string(), "\" a\\nb \"".to_string()),
+    ("\" \\\\ \" length".to_string(), "1".to_string()),
+    ("\" \\u{e9}\\u{1F600} \"".to_string(), "\" é😀 \"".to_string()),
+    ("\" é😀 \" length".to_string(), "2".to_string()),
+    ("\" \\u{7} \"".to_string(), "\" \\u{7} \"".to_string()),
+    ("[ \" a } b \" { \" ] \" } ]".to_string(), "[ \" a } b \" { \" ] \" } ]".to_string()),
+    ("{ \" x  y \" length } exec".to_string(), "4".to_string()),
+    ("\" a \\q \"".to_string(), "InvalidEscape".to_string()),
+    ("\" \\u{110000} \"".to_string(), "InvalidEscape".to_string()),
+    ("\" a \\\" b".to_string(), "IncompleteString".to_string()),
+
+    // Introspection
+    ("words head".to_string(), "'+".to_string()),
+    ("words length 60 >".to_string(), "True".to_string()),
+    ("words filter { 'sum == } length".to_string(), "1".to_string()),
+    ("sq { dup * } fun words filter { 'sq == }".to_string(), "[ 'sq ]".to_string()),
+    ("help +".to_string(), "\" + ( x y -- x+y ) Adds two numbers. \"".to_string()),
+    ("help 'dup".to_string(), "\" dup ( x -- x x ) Duplicates the top element. \"".to_string()),
+    ("sq { dup * } fun help sq".to_string(), "\" sq user function { dup * } \"".to_string()),
+    ("help nothing".to_string(), "UnknownWord".to_string()),
+    ("1 2 dupp".to_string(), "UnknownWord".to_string()),
+    ("'inc { 1 + } fun 'inc { 2 + } fun 1 inc".to_string(), "3".to_string()),
+    ("1 typeOf".to_string(), "\" int \"".to_string()),
+    ("[ 1 1.0 1/2 True \" a \" [ ] { } 'x ] map typeOf".to_string(), "[ \" int \" \" float \" \" rational \" \" bool \" \" string \" \" list \" \" quotation \" \" symbol \" ]".to_string()),
+    ("1 iterate { 1 + } typeOf".to_string(), "\" sequence \"".to_string()),
+    ("{ 1 } case { quotation { exec } _ { pop 0 } }".to_string(), "1".to_string()),
+
+    // Records
+    ("point [ x y ] struct 1 2 point point.y".to_string(), "2".to_string()),
+    ("point [ x y ] struct 1 2 point dup point.x swap point.y [ ] cons cons".to_string(), "[ 1 2 ]".to_string()),
+    ("point [ x y ] struct 1 2 point 'x field".to_string(), "1".to_string()),
+    ("point [ x y ] struct 1 2 point 'z field".to_string(), "NoSuchField".to_string()),
+    ("1 'x field".to_string(), "ExpectedRecord".to_string()),
+    ("1 2 'pair [ a b ] record 'b field".to_string(), "2".to_string()),
+    ("point [ x y ] struct 1 point".to_string(), "StackEmpty".to_string()),
+    ("point [ x y ] struct 1 2 point typeOf".to_string(), "\" record \"".to_string()),
+    ("point [ x y ] struct 1 2 point case { record { point.x } _ { 0 } }".to_string(), "1".to_string()),
+    ("point [ x y ] struct 1 2 point 1 2 point ==".to_string(), "True".to_string()),
+    ("point [ x y ] struct 1 2 point 2 1 point ==".to_string(), "False".to_string()),
+    ("point [ x y ] struct 1 2 point 3 4 point +".to_string(), "ExpectedNumber".to_string()),
+    ("point [ x y ] struct point.+ { | a b | a point.x b point.x + a point.y b point.y + point } fun 1 2 point 3 4 point + point.y".to_string(), "6".to_string()),
+    ("point [ x y ] struct point.* { | p k | p point.x k * p point.y k * point } fun 1 2 point 3 * point.x".to_string(), "3".to_string()),
+    ("point [ x y ] struct point.== { | a b | a point.x b point.x == } fun 1 2 point 1 5 point ==".to_string(), "True".to_string()),
+    ("point [ x y ] struct point.== { | a b | a point.x b point.x == } fun 1 2 point 1 5 point !=".to_string(), "False".to_string()),
+    ("point [ x y ] struct 1 2 +".to_string(), "3".to_string()),
+    ("point [ x y ] struct 1 2 point".to_string(), "( 1 2 'point [ x y ] record )".to_string()),
+    ("point [ x y ] struct 1 2 point [ ] cons".to_string(), "[ ( 1 2 'point [ x y ] record ) ]".to_string()),
+    ("( 1 2 'point [ x y ] record ) 'y field".to_string(), "2".to_string()),
+    ("( 1 'point [ x y ] record )".to_string(), "InvalidOperation".to_string()),
+    ("point [ x y ] struct point.== { | a b | a point.x b point.x == } fun 1 5 point 1 2 point 3 4 point [ ] cons cons cons dedup".to_string(), "[ ( 1 5 'point [ x y ] record ) ( 3 4 'point [ x y ] record ) ]".to_string()),
+    ("point [ x y ] struct point.== { | a b | a point.x b point.x == } fun 1 5 point 1 2 point [ ] cons cons sort".to_string(), "[ ( 1 2 'point [ x y ] record ) ( 1 5 'point [ x y ] record ) ]".to_string()),
+    ("point [ x y ] struct point.== { | a b | a point.x b point.x == } fun 1 5 point case { ( 1 2 'point [ x y ] record ) { \" same x \" } _ { pop \" other \" } }".to_string(), "\" same x \"".to_string()),
+    ("point [ x y ] struct 1 5 point case { ( 1 2 'point [ x y ] record ) { \" same x \" } _ { pop \" other \" } }".to_string(), "\" other \"".to_string()),
 
     // Lists
-    ("[ 1 2 3 ]".to_string(), "[1,2,3]".to_string()),
-    ("[ 1 \" bob \" ]".to_string(), "[1,\" bob \"]".to_string()),
+    ("[ 1 2 3 ]".to_string(), "[ 1 2 3 ]".to_string()),
+    ("[ 1 \" bob \" ]".to_string(), "[ 1 \" bob \" ]".to_string()),
     ("[ 1 2 ] empty".to_string(), "False".to_string()),
     ("[ ] empty".to_string(), "True".to_string()),
     ("[ 1 2 3 ] head".to_string(), "1".to_string()),
     ("[ 1 2 3 ] length".to_string(), "3".to_string()),
-    ("[ 1 2 3 ] tail".to_string(), "[2,3]".to_string()),
-    ("1 [ ] cons".to_string(), "[1]".to_string()),
-    ("1 [ 2 3 ] cons".to_string(), "[1,2,3]".to_string()),
-    ("[ 1 2 ] [ ] append".to_string(), "[1,2]".to_string()),
-    ("[ 1 ] [ 2 3 ] append".to_string(), "[1,2,3]".to_string()),
-    ("[ 1 ] [ 2 3 ] cons".to_string(), "[[1],2,3]".to_string()),
+    ("[ 1 2 3 ] tail".to_string(), "[ 2 3 ]".to_string()),
+    ("1 [ ] cons".to_string(), "[ 1 ]".to_string()),
+    ("1 [ 2 3 ] cons".to_string(), "[ 1 2 3 ]".to_string()),
+    ("[ 1 2 ] [ ] append".to_string(), "[ 1 2 ]".to_string()),
+    ("[ 1 ] [ 2 3 ] append".to_string(), "[ 1 2 3 ]".to_string()),
+    ("[ 1 ] [ 2 3 ] cons".to_string(), "[ [ 1 ] 2 3 ]".to_string()),
 
     
     // If statements
@@ -775,16 +525,16 @@ fn tests() {
     ("True if { False if 50 100 } 30".to_string(), "100".to_string()),
 
     // List quotations
-    ("[ 1 2 3 ] map { 10 * }".to_string(), "[10,20,30]".to_string()),
-    ("[ 1 2 3 ] map { 1 + }".to_string(), "[2,3,4]".to_string()),
-    ("[ 1 2 3 4 ] map { dup 2 > if { 10 * } { 2 * } }".to_string(), "[2,4,30,40]".to_string()),
+    ("[ 1 2 3 ] map { 10 * }".to_string(), "[ 10 20 30 ]".to_string()),
+    ("[ 1 2 3 ] map { 1 + }".to_string(), "[ 2 3 4 ]".to_string()),
+    ("[ 1 2 3 4 ] map { dup 2 > if { 10 * } { 2 * } }".to_string(), "[ 2 4 30 40 ]".to_string()),
     ("[ 1 2 3 4 ] each { 10 * } + + +".to_string(), "100".to_string()),
     ("[ 1 2 3 4 ] 0 foldl { + }".to_string(), "10".to_string()), 
     ("[ 2 5 ] 20 foldl { div }".to_string(), "2".to_string()),
-    ("[ \" 1 \" \" 2 \" \" 3 \" ] each { parseInteger } [ ] cons cons cons".to_string(), "[1,2,3]".to_string()), 
+    ("[ \" 1 \" \" 2 \" \" 3 \" ] each { parseInteger } [ ] cons cons cons".to_string(), "[ 1 2 3 ]".to_string()), 
     ("[ 1 2 3 4 ] 0 foldl +".to_string(), "10".to_string()),
     ("[ 2 5 ] 20 foldl div".to_string(), "2".to_string()),
-    ("[ \" 1 \" \" 2 \" \" 3 \" ] each parseInteger [ ] 3 times cons".to_string(), "[1,2,3]".to_string()),
+    ("[ \" 1 \" \" 2 \" \" 3 \" ] each parseInteger [ ] 3 times cons".to_string(), "[ 1 2 3 ]".to_string()),
 
 
 
@@ -793,47 +543,140 @@ fn tests() {
     ("10 { 20 + } exec".to_string(), "30".to_string()),
     ("10 20 { + } exec".to_string(), "30".to_string()),
     ("{ { 10 20 + } exec } exec".to_string(), "30".to_string()),
-    //("{ { 10 20 + } exec 20 + } exec".to_string(), "50".to_string()),
+    ("{ { 10 20 + } exec 20 + } exec".to_string(), "50".to_string()),
     
 
     // Times
      ("1 times { 100 50 + }".to_string(), "150".to_string()),
-    //("5 times { 1 } [ ] 5 times { cons } 0 foldl { + }".to_string(), "5".to_string()),
-    // ("5 times 1 [ ] 5 times cons 0 foldl +".to_string(), "5".to_string()),
-    // ("5 times { 10 } + + + +".to_string(), "50".to_string()),
-    // ("5 times 10 4 times +".to_string(), "50".to_string()),
+    ("5 times { 1 } [ ] 5 times { cons } 0 foldl { + }".to_string(), "5".to_string()),
+    ("5 times 1 [ ] 5 times cons 0 foldl +".to_string(), "5".to_string()),
+    ("5 times { 10 } + + + +".to_string(), "50".to_string()),
+    ("5 times 10 4 times +".to_string(), "50".to_string()),
     
 
     // // Assignments
     // ("age".to_string(), "age".to_string()),
     // ("age 10 := age".to_string(), "10".to_string()),
     // ("10 age swap := age".to_string(), "10".to_string()),
-    // ("[ 1 2 3 ] list swap := list".to_string(), "[1,2,3]".to_string()),
-    // ("age 20 := [ 10 age ]".to_string(), "[10,20]".to_string()),
-
-    // // Functions
-    // ("inc { 1 + } fun 1 inc".to_string(), "2".to_string()),
-    // ("mul10 { 10 * } fun inc { 1 + } fun 10 inc mul10".to_string(), "110".to_string()),
+    // ("[ 1 2 3 ] list swap := list".to_string(), "[ 1 2 3 ]".to_string()),
+    // ("age 20 := [ 10 age ]".to_string(), "[ 10 20 ]".to_string()),
+
+    // Functions
+    ("inc { 1 + } fun 1 inc".to_string(), "2".to_string()),
+    ("mul10 { 10 * } fun inc { 1 + } fun 10 inc mul10".to_string(), "110".to_string()),
+
+    // Quoted words
+    ("'+".to_string(), "'+".to_string()),
+    ("unknown".to_string(), "UnknownWord".to_string()),
+    ("[ '+ 'map ]".to_string(), "[ '+ 'map ]".to_string()),
+    ("1 2 '+ exec".to_string(), "3".to_string()),
+    ("[ 1 2 3 ] 0 foldl '+".to_string(), "6".to_string()),
+    ("inc { 1 + } fun [ 1 2 3 ] map 'inc".to_string(), "[ 2 3 4 ]".to_string()),
+    ("inc { 1 + } fun 2 'inc exec".to_string(), "3".to_string()),
+    ("inc { 1 + } fun 'inc { 2 + } fun 1 inc".to_string(), "3".to_string()),
+    ("1 2 True if '+ '-".to_string(), "3".to_string()),
+    ("2 3 times 'dup + + +".to_string(), "8".to_string()),
+    ("[ 1 2 ] 'map exec".to_string(), "ExpectedQuotation".to_string()),
+
+    // Case
+    ("1 case { 0 { \" zero \" } 1 { \" one \" } _ { pop \" many \" } }".to_string(), "\" one \"".to_string()),
+    ("5 case { 0 { \" zero \" } _ { 2 * } }".to_string(), "10".to_string()),
+    ("2.5 case { int { \" int \" } float { pop \" float \" } }".to_string(), "\" float \"".to_string()),
+    ("\" hi \" case { string { length } _ { pop 0 } }".to_string(), "2".to_string()),
+    ("True case { bool { not } }".to_string(), "False".to_string()),
+    ("[ 1 2 3 ] case { [ ] { 0 } [ x | xs ] { xs } }".to_string(), "[ 2 3 ]".to_string()),
+    ("[ ] case { [ ] { 0 } [ x | xs ] { x } }".to_string(), "0".to_string()),
+    ("[ 1 ] case { [ 1 ] { True } _ { pop False } }".to_string(), "True".to_string()),
+    ("len { case { [ ] { 0 } [ x | xs ] { xs len 1 + } } } fun [ 4 5 6 ] len".to_string(), "3".to_string()),
+    ("3 case { 1 { 1 } }".to_string(), "NoMatch".to_string()),
+    ("3 case 1".to_string(), "ExpectedQuotation".to_string()),
+
+    // Parallel map and filter
+    ("[ 1 2 3 4 5 6 7 8 9 10 ] pmap { dup * }".to_string(), "[ 1 4 9 16 25 36 49 64 81 100 ]".to_string()),
+    ("[ ] pmap { 1 + }".to_string(), "[ ]".to_string()),
+    ("[ 1 2 3 4 5 6 ] pfilter { 2 div 2 * 1 + 0 > }".to_string(), "[ 1 2 3 4 5 6 ]".to_string()),
+    ("[ 1 2 3 4 5 6 ] pfilter { 3 < }".to_string(), "[ 1 2 ]".to_string()),
+    ("square { dup * } fun [ 1 2 3 ] pmap square".to_string(), "[ 1 4 9 ]".to_string()),
+    ("10 { | n | [ 1 2 3 ] pmap { n + } } exec".to_string(), "[ 11 12 13 ]".to_string()),
+    ("1 iterate { 1 + } 5 take pmap { 10 * }".to_string(), "[ 10 20 30 40 50 ]".to_string()),
+    ("[ 1 2 ] pmap { x { 1 } fun 1 }".to_string(), "ImpureOperation".to_string()),
+    ("[ 1 2 ] pmap { 3 precision }".to_string(), "ImpureOperation".to_string()),
+    ("[ 1 2 ] pmap { import \" lib.bprog \" }".to_string(), "ImpureOperation".to_string()),
+    ("f { y { 1 } fun } fun [ 1 ] pfilter f".to_string(), "ImpureOperation".to_string()),
+    ("[ 1 0 2 [ ] ] pmap { 1 swap div }".to_string(), "DivisionByZero".to_string()),
+    ("[ 1 [ ] 2 0 ] pmap { 1 swap div }".to_string(), "ExpectedNumber".to_string()),
+    ("[ 1 2 ] pfilter { 1 }".to_string(), "ExpectedBool".to_string()),
+    ("1 pmap { 1 + }".to_string(), "ExpectedList".to_string()),
+
+    // Lazy sequences
+    ("1 iterate { 1 + } 5 take toList".to_string(), "[ 1 2 3 4 5 ]".to_string()),
+    ("1 iterate { 2 * } takeWhile { 100 < } toList".to_string(), "[ 1 2 4 8 16 32 64 ]".to_string()),
+    ("1 iterate { 1 + } map { dup * } 4 take toList".to_string(), "[ 1 4 9 16 ]".to_string()),
+    ("1 iterate { 1 + } filter { dup 2 div 2 * == } 3 take toList".to_string(), "[ 2 4 6 ]".to_string()),
+    ("1 iterate { 1 + } 5 take filter { 2 > } toList".to_string(), "[ 3 4 5 ]".to_string()),
+    ("1 iterate { dup 3 == if { \" x \" + } { 1 + } } 3 take toList".to_string(), "[ 1 2 3 ]".to_string()),
+    ("5 iterate { 1 + } 0 take toList".to_string(), "[ ]".to_string()),
+    ("1 iterate { 1 + } map { 2 * } 3 take".to_string(), "( 1 iterate { 1 + } map { 2 * } 3 take )".to_string()),
+    ("1 iterate { 1 + } 2 take [ ] cons".to_string(), "[ ( 1 iterate { 1 + } 2 take ) ]".to_string()),
+    ("[ ( 1 iterate { 2 * } takeWhile { 10 < } ) 5 ]".to_string(), "[ ( 1 iterate { 2 * } takeWhile { 10 < } ) 5 ]".to_string()),
+    ("( 1 iterate { 1 + } filter { 2 > } 2 take ) toList".to_string(), "[ 3 4 ]".to_string()),
+    ("( 1 iterate { 1 + } 2 take".to_string(), "IncompleteValue".to_string()),
+    ("( 1 2 iterate { 1 + } )".to_string(), "InvalidOperation".to_string()),
+    ("1 iterate { 1 + } toList".to_string(), "UnboundedSequence".to_string()),
+    ("1 iterate { 1 + } map { 2 * } pmap { 1 + }".to_string(), "UnboundedSequence".to_string()),
+    ("[ 1 2 3 4 ] filter { 2 > }".to_string(), "[ 3 4 ]".to_string()),
+    ("[ 1 2 3 ] 2 take".to_string(), "[ 1 2 ]".to_string()),
+    ("[ 1 2 3 1 ] takeWhile { 3 < }".to_string(), "[ 1 2 ]".to_string()),
+    ("1 toList".to_string(), "ExpectedList".to_string()),
+
+    // Prelude
+    ("[ 1 2 3 4 ] sum".to_string(), "10".to_string()),
+    ("[ 1 2 3 4 ] product".to_string(), "24".to_string()),
+    ("[ 3 9 2 ] max".to_string(), "9".to_string()),
+    ("[ 3 9 2 ] min".to_string(), "2".to_string()),
+    ("[ 1.5 3 2 ] max".to_string(), "3".to_string()),
+    ("1 [ 3 9 2 ] min".to_string(), "ProgramFinishedWithMultipleValues".to_string()),
+    ("[ ] max".to_string(), "InvalidOperation".to_string()),
+    ("[ 1 \" a \" ] max".to_string(), "ExpectedNumber".to_string()),
+
+    // Modules
+    ("import \" shapes.bprog \" 3 shapes.square".to_string(), "9".to_string()),
+    ("import \"shapes.bprog\" 2 shapes.cube".to_string(), "8".to_string()),
+    ("import \" shapes.bprog \" import \" shapes.bprog \" 3 shapes.square".to_string(), "9".to_string()),
+    ("import \" cycle_a.bprog \"".to_string(), "ImportCycle".to_string()),
+    ("import \" missing.bprog \"".to_string(), "ModuleNotFound".to_string()),
 
 
     // // Loop
-    // ("1 loop { dup 4 > } { dup 1 + } [ ] 5 times { cons }".to_string(), "[1,2,3,4,5]".to_string()),
-    // ("1 loop { dup 4 > } { dup 1 + } [ ] 5 times cons".to_string(), "[1,2,3,4,5]".to_string()),
-    // ("[ 1 ] loop { dup length 9 > } { dup head 1 + swap cons }".to_string(), "[10,9,8,7,6,5,4,3,2,1]".to_string()),
+    // ("1 loop { dup 4 > } { dup 1 + } [ ] 5 times { cons }".to_string(), "[ 1 2 3 4 5 ]".to_string()),
+    // ("1 loop { dup 4 > } { dup 1 + } [ ] 5 times cons".to_string(), "[ 1 2 3 4 5 ]".to_string()),
+    // ("[ 1 ] loop { dup length 9 > } { dup head 1 + swap cons }".to_string(), "[ 10 9 8 7 6 5 4 3 2 1 ]".to_string()),
 
     ];
 
     println!("Running tests...");
+    interpreter.search_path.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test-modules"));
 
     let mut tests_passed : usize = 0;
     for (index, (input, output)) in testings.iter().enumerate() {
         println!("Test {} passed", index+1);
         tests_passed = index;
-        let result = match interpreter(&input){
-            Ok(value) => format_stack_item(value),
-            Err(e) => format!("{:?}", e),
+        interpreter.reset();
+        let (result, succeeded) = match interpreter.run(input){
+            Ok(value) => (interpreter.format(value), true),
+            Err(e) => (format!("{:?}", e), false),
         };
         assert!(result == *output, "FAIL on test {}\n- test: {}\n- result: {}\n- expected: {}", index, input, result, output);
+
+        //Printed values have to parse back to the same value
+        if succeeded {
+            interpreter.reset();
+            let reprinted = match interpreter.run(&result) {
+                Ok(value) => format_stack_item(value),
+                Err(e) => format!("{:?}", e),
+            };
+            assert!(reprinted == result, "FAIL on test {}, printed value does not parse back\n- printed: {}\n- parsed: {}", index, result, reprinted);
+        }
     }
 
     println!("{} successful tests!", tests_passed+1);
@@ -0,0 +1,283 @@
+//Arithmetic and the math words, with one set of promotion rules for all of them:
+//- two ints give an int, an int meeting a rational is turned into a rational and gives a rational, and
+//  anything meeting a float is turned into a float first and gives a float
+//- an int or rational result too large for an int is given as a float instead
+//- rationals are exact fractions like `1/3`, always in lowest terms with a positive denominator
+//- `/` gives a float for ints and floats and a rational when a rational is involved, `div` is floored
+//  division giving an int for ints and rationals and a whole float otherwise. Dividing by zero gives inf
+//  or NaN for floats and `/` of two ints, and DivisionByZero for rationals and `div` of two ints
+//- floor, ceil, round and toInt give an int, sqrt and toFloat a float
+//- abs, min and max give back one of their numbers as it is, min and max the first one when both are equal
+//- pow of two ints is an int when the exponent is not negative, a float otherwise. A rational to the
+//  power of an int is a rational
+use core::cmp::Ordering;
+
+use crate::compare::compare;
+use crate::{Datatype, ProgramError};
+
+//The two top numbers promoted to the same type, the one below the top first
+enum Numbers {
+    Ints(i128, i128),
+    //Numerator and denominator of each
+    Rationals((i128, i128), (i128, i128)),
+    Floats(f64, f64),
+}
+
+fn numbers(a : Datatype, b : Datatype) -> Result<Numbers, ProgramError> {
+    match (a, b) {
+        (Datatype::Int(a), Datatype::Int(b)) => Ok(Numbers::Ints(b, a)),
+        (a @ Datatype::Float(_), b) | (a, b @ Datatype::Float(_)) => Ok(Numbers::Floats(float(b)?, float(a)?)),
+        (a, b) => Ok(Numbers::Rationals(fraction(b)?, fraction(a)?)),
+    }
+}
+
+fn float(a : Datatype) -> Result<f64, ProgramError> {
+    match a {
+        Datatype::Int(a) => Ok(a as f64),
+        Datatype::Rational(n, d) => Ok(n as f64 / d as f64),
+        Datatype::Float(a) => Ok(a),
+        _ => Err(ProgramError::ExpectedNumber),
+    }
+}
+
+//Numerator and denominator of an int or a rational
+fn fraction(a : Datatype) -> Result<(i128, i128), ProgramError> {
+    match a {
+        Datatype::Int(a) => Ok((a, 1)),
+        Datatype::Rational(n, d) => Ok((n, d)),
+        _ => Err(ProgramError::ExpectedNumber),
+    }
+}
+
+//On the magnitudes, so that i128::MIN does not overflow
+fn gcd(mut a : u128, mut b : u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+//The rational n/d in lowest terms, an int when the denominator is 1. None when it does not fit, which
+//only happens when -i128::MIN would be needed
+pub fn rational(n : i128, d : i128) -> Result<Option<Datatype>, ProgramError> {
+    if d == 0 {
+        return Err(ProgramError::DivisionByZero);
+    }
+    let negative = (n < 0) != (d < 0);
+    let divisor = gcd(n.unsigned_abs(), d.unsigned_abs());
+    let (n, d) = (n.unsigned_abs() / divisor, d.unsigned_abs() / divisor);
+
+    let n = if negative { 0i128.checked_sub_unsigned(n) } else { i128::try_from(n).ok() };
+    match (n, i128::try_from(d)) {
+        (Some(n), Ok(1)) => Ok(Some(Datatype::Int(n))),
+        (Some(n), Ok(d)) => Ok(Some(Datatype::Rational(n, d))),
+        _ => Ok(None),
+    }
+}
+
+//The rational result, or the float one when the numerator or denominator overflows
+fn rational_or_float(fraction : Option<(i128, i128)>, float : f64) -> Result<Datatype, ProgramError> {
+    match fraction {
+        Some((n, d)) => Ok(rational(n, d)?.unwrap_or(Datatype::Float(float))),
+        None => Ok(Datatype::Float(float)),
+    }
+}
+
+//The int below a rational, which is already in lowest terms with a positive denominator
+fn floor_fraction(n : i128, d : i128) -> i128 {
+    n.div_euclid(d)
+}
+
+//Floats outside the range of an int, and NaN, have no int to become
+fn whole(a : f64) -> Result<Datatype, ProgramError> {
+    if a >= i128::MIN as f64 && a < i128::MAX as f64 {
+        Ok(Datatype::Int(a as i128))
+    } else {
+        Err(ProgramError::InvalidOperation)
+    }
+}
+
+//The int result, or the float one when the int operation overflows
+fn int_or_float(int : Option<i128>, float : f64) -> Datatype {
+    match int {
+        Some(value) => Datatype::Int(value),
+        None => Datatype::Float(float),
+    }
+}
+
+pub fn add(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    match numbers(a, b)? {
+        Numbers::Ints(x, y) => Ok(int_or_float(x.checked_add(y), x as f64 + y as f64)),
+        Numbers::Rationals((a, b), (c, d)) => {
+            let sum = a.checked_mul(d).zip(c.checked_mul(b)).and_then(|(x, y)| x.checked_add(y)).zip(b.checked_mul(d));
+            rational_or_float(sum, a as f64 / b as f64 + c as f64 / d as f64)
+        },
+        Numbers::Floats(x, y) => Ok(Datatype::Float(x + y)),
+    }
+}
+
+pub fn subtract(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    match numbers(a, b)? {
+        Numbers::Ints(x, y) => Ok(int_or_float(x.checked_sub(y), x as f64 - y as f64)),
+        Numbers::Rationals((a, b), (c, d)) => {
+            let difference = a.checked_mul(d).zip(c.checked_mul(b)).and_then(|(x, y)| x.checked_sub(y)).zip(b.checked_mul(d));
+            rational_or_float(difference, a as f64 / b as f64 - c as f64 / d as f64)
+        },
+        Numbers::Floats(x, y) => Ok(Datatype::Float(x - y)),
+    }
+}
+
+pub fn multiply(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    match numbers(a, b)? {
+        Numbers::Ints(x, y) => Ok(int_or_float(x.checked_mul(y), x as f64 * y as f64)),
+        Numbers::Rationals((a, b), (c, d)) => {
+            let product = a.checked_mul(c).zip(b.checked_mul(d));
+            rational_or_float(product, (a as f64 / b as f64) * (c as f64 / d as f64))
+        },
+        Numbers::Floats(x, y) => Ok(Datatype::Float(x * y)),
+    }
+}
+
+pub fn divide(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    match numbers(a, b)? {
+        Numbers::Ints(x, y) => Ok(Datatype::Float(x as f64 / y as f64)),
+        Numbers::Rationals(_, (0, _)) => Err(ProgramError::DivisionByZero),
+        Numbers::Rationals((a, b), (c, d)) => {
+            let quotient = a.checked_mul(d).zip(b.checked_mul(c));
+            rational_or_float(quotient, (a as f64 / b as f64) / (c as f64 / d as f64))
+        },
+        Numbers::Floats(x, y) => Ok(Datatype::Float(x / y)),
+    }
+}
+
+pub fn div(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    match numbers(a, b)? {
+        Numbers::Ints(_, 0) => Err(ProgramError::DivisionByZero),
+        //Only the smallest int divided by -1 overflows
+        Numbers::Ints(x, -1) => Ok(int_or_float(x.checked_neg(), -(x as f64))),
+        Numbers::Ints(x, y) => {
+            let quotient = x / y;
+            //Rounds towards negative infinity like the float division below, not towards zero
+            if x % y != 0 && (x < 0) != (y < 0) {
+                Ok(Datatype::Int(quotient - 1))
+            } else {
+                Ok(Datatype::Int(quotient))
+            }
+        },
+        Numbers::Rationals((a, b), (c, d)) => match divide(Datatype::Rational(c, d), Datatype::Rational(a, b))? {
+            Datatype::Rational(n, d) => Ok(Datatype::Int(floor_fraction(n, d))),
+            quotient => floor(quotient),
+        },
+        Numbers::Floats(x, y) => Ok(Datatype::Float(libm::floor(x / y))),
+    }
+}
+
+pub fn pow(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    match numbers(a, b)? {
+        Numbers::Ints(x, y) => {
+            let power = u32::try_from(y).ok().and_then(|y| x.checked_pow(y));
+            Ok(int_or_float(power, libm::pow(x as f64, y as f64)))
+        },
+        //Only whole exponents keep a rational exact
+        Numbers::Rationals((a, b), (y, 1)) => {
+            let (a, b) = if y < 0 { (b, a) } else { (a, b) };
+            let power = u32::try_from(y.unsigned_abs()).ok().and_then(|y| a.checked_pow(y).zip(b.checked_pow(y)));
+            rational_or_float(power, libm::pow(a as f64 / b as f64, y.unsigned_abs() as f64))
+        },
+        Numbers::Rationals((a, b), (c, d)) => Ok(Datatype::Float(libm::pow(a as f64 / b as f64, c as f64 / d as f64))),
+        Numbers::Floats(x, y) => Ok(Datatype::Float(libm::pow(x, y))),
+    }
+}
+
+pub fn min(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    float(a.clone())?;
+    float(b.clone())?;
+    match compare(&a, &b) {
+        Ordering::Less => Ok(a),
+        _ => Ok(b),
+    }
+}
+
+pub fn max(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    float(a.clone())?;
+    float(b.clone())?;
+    match compare(&a, &b) {
+        Ordering::Greater => Ok(a),
+        _ => Ok(b),
+    }
+}
+
+pub fn abs(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::Int(a) => Ok(int_or_float(a.checked_abs(), -(a as f64))),
+        Datatype::Rational(n, d) => rational_or_float(n.checked_abs().map(|n| (n, d)), -(n as f64) / d as f64),
+        Datatype::Float(a) => Ok(Datatype::Float(libm::fabs(a))),
+        _ => Err(ProgramError::ExpectedNumber),
+    }
+}
+
+pub fn floor(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::Float(a) => whole(libm::floor(a)),
+        Datatype::Rational(n, d) => Ok(Datatype::Int(floor_fraction(n, d))),
+        a => float(a.clone()).map(|_| a),
+    }
+}
+
+pub fn ceil(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::Float(a) => whole(libm::ceil(a)),
+        //The denominator is never 1 or less, so a rational always lies strictly between two ints
+        Datatype::Rational(n, d) => Ok(Datatype::Int(floor_fraction(n, d) + 1)),
+        a => float(a.clone()).map(|_| a),
+    }
+}
+
+//Halfway cases round away from zero
+pub fn round(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::Float(a) => whole(libm::round(a)),
+        Datatype::Rational(n, d) => {
+            let (quotient, remainder) = (n / d, n % d);
+            //|remainder| / d is at least a half
+            if remainder.abs() >= d - remainder.abs() {
+                Ok(Datatype::Int(quotient + n.signum()))
+            } else {
+                Ok(Datatype::Int(quotient))
+            }
+        },
+        a => float(a.clone()).map(|_| a),
+    }
+}
+
+//Drops the fraction, rounding towards zero
+pub fn to_int(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::Float(a) => whole(libm::trunc(a)),
+        Datatype::Rational(n, d) => Ok(Datatype::Int(n / d)),
+        a => float(a.clone()).map(|_| a),
+    }
+}
+
+pub fn to_float(a : Datatype) -> Result<Datatype, ProgramError> {
+    float(a).map(Datatype::Float)
+}
+
+//An int is its own numerator, over 1
+pub fn numerator(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::Float(_) => Err(ProgramError::InvalidOperation),
+        a => fraction(a).map(|(n, _)| Datatype::Int(n)),
+    }
+}
+
+pub fn denominator(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::Float(_) => Err(ProgramError::InvalidOperation),
+        a => fraction(a).map(|(_, d)| Datatype::Int(d)),
+    }
+}
+
+pub fn sqrt(a : Datatype) -> Result<Datatype, ProgramError> {
+    float(a).map(|a| Datatype::Float(libm::sqrt(a)))
+}
@@ -0,0 +1,141 @@
+//Loading bprog source from files: programs, `import`ed modules and the prelude
+use alloc::string::String;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::{Component, Path, PathBuf};
+
+#[cfg(feature = "std")]
+use crate::Datatype;
+use crate::{Interpreter, ProgramError};
+
+//The standard prelude is written in bprog and compiled into the interpreter
+pub const PRELUDE : &str = include_str!("../prelude.bprog");
+
+impl Interpreter {
+    pub fn load_prelude(&mut self) {
+        if let Err(e) = self.load(PRELUDE, None) {
+            panic!("Error in the prelude: {:?}", e);
+        }
+    }
+
+    //Runs module code on a stack of its own, so that only the definitions are kept
+    fn load(&mut self, source : &str, namespace : Option<String>) -> Result<(), ProgramError> {
+        let stack = core::mem::take(&mut self.stack);
+        let namespace = core::mem::replace(&mut self.namespace, namespace);
+
+        let result = self.execute(source);
+        self.stack = stack;
+        self.namespace = namespace;
+        result
+    }
+
+    //Without std there is no file system to import from
+    #[cfg(not(feature = "std"))]
+    pub fn import(&mut self, _path : &str) -> Result<(), ProgramError> {
+        Err(ProgramError::ModuleNotFound)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Interpreter {
+
+    //Runs a program file, imports inside it are looked up next to the file first
+    pub fn run_file(&mut self, path : &str) -> Result<Datatype, ProgramError> {
+        let file = fs::canonicalize(path).map_err(|_| ProgramError::ModuleNotFound)?;
+        let program = fs::read_to_string(&file).map_err(|_| ProgramError::ModuleNotFound)?;
+
+        self.importing.push(file);
+        let result = self.run(&program);
+        self.importing.pop();
+        result
+    }
+
+    //Loads the functions of a module into a namespace named after the file, e.g. `lib.sum` for lib.bprog
+    pub fn import(&mut self, path : &str) -> Result<(), ProgramError> {
+        let file = self.find_module(path).ok_or(ProgramError::ModuleNotFound)?;
+        if self.importing.contains(&file) {
+            return Err(ProgramError::ImportCycle);
+        }
+        if self.imported.contains(&file) {
+            return Ok(());
+        }
+
+        let source = match self.embedded.get(&file) {
+            Some(source) => source.clone(),
+            None => fs::read_to_string(&file).map_err(|_| ProgramError::ModuleNotFound)?,
+        };
+        let namespace = match file.file_stem() {
+            Some(stem) => stem.to_string_lossy().into_owned(),
+            None => return Err(ProgramError::ModuleNotFound),
+        };
+
+        self.importing.push(file.clone());
+        let result = self.load(&source, Some(namespace));
+        self.importing.pop();
+
+        result?;
+        self.imported.insert(file);
+        Ok(())
+    }
+
+    //Embeds a module in the program, imported as the file at the path relative to the program would be
+    pub fn add_module(&mut self, path : &str, source : &str) {
+        self.embedded.insert(PathBuf::from(path), source.to_string());
+    }
+
+    //Relative paths are tried next to the importing file, then in the search path and last in the working directory.
+    //A compiled program looks among its embedded modules first
+    fn find_module(&self, path : &str) -> Option<PathBuf> {
+        let path = Path::new(path);
+        if let Some(module) = self.embedded_module(path) {
+            return Some(module);
+        }
+        let mut candidates : Vec<PathBuf> = Vec::new();
+
+        if path.is_absolute() {
+            candidates.push(path.to_path_buf());
+        } else {
+            if let Some(directory) = self.importing.last().and_then(|file| file.parent()) {
+                candidates.push(directory.join(path));
+            }
+            for directory in &self.search_path {
+                candidates.push(directory.join(path));
+            }
+            candidates.push(path.to_path_buf());
+        }
+
+        candidates.into_iter()
+            .find(|candidate| candidate.is_file())
+            .and_then(|file| fs::canonicalize(file).ok())
+    }
+
+    fn embedded_module(&self, path : &Path) -> Option<PathBuf> {
+        let directory = match self.importing.last() {
+            Some(file) if self.embedded.contains_key(file) => file.parent()?.to_path_buf(),
+            Some(_) => return None,
+            None => PathBuf::new(),
+        };
+        embedded_path(&directory, path).filter(|module| self.embedded.contains_key(module))
+    }
+}
+
+//The path of a module relative to the directory of a compiled program, None for an absolute path or one
+//leading out of that directory
+#[cfg(feature = "std")]
+pub(crate) fn embedded_path(directory : &Path, path : &Path) -> Option<PathBuf> {
+    let mut resolved = directory.to_path_buf();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => resolved.push(name),
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return None;
+                }
+            },
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(resolved)
+}
@@ -0,0 +1,121 @@
+//`pmap` and `pfilter`: like `map` and `filter`, with the quotation run on a pool of worker threads. Every
+//worker has an interpreter of its own with the caller's functions and local names, in which the words that
+//read files or change definitions (`import`, `fun` and `precision`) fail with ImpureOperation, so that the
+//result does not depend on the order the elements are run in. The results keep the order of the list,
+//and when several elements fail the error of the first one is given. The steps of the workers count
+//against the step limit of the caller, as does starting each of them. Inside a worker `pmap` and `pfilter` run
+//the elements on the worker's own thread, so that nested calls do not add threads
+use alloc::vec::Vec;
+
+use crate::{Datatype, Interpreter, ProgramError};
+
+impl Interpreter {
+    pub(crate) fn pmap(&mut self, list : Datatype, tokens : &mut Vec<&str>) -> Result<Datatype, ProgramError> {
+        let operation = self.read_quotation(tokens)?;
+        let items = self.items(list)?;
+        let results = self.parallel(&items, |worker, item| worker.apply(&operation, item))?;
+        self.listed(results)
+    }
+
+    pub(crate) fn pfilter(&mut self, list : Datatype, tokens : &mut Vec<&str>) -> Result<Datatype, ProgramError> {
+        let predicate = self.read_quotation(tokens)?;
+        let items = self.items(list)?;
+        let keep = self.parallel(&items, |worker, item| worker.test(&predicate, item))?;
+        Ok(Datatype::List(items.into_iter().zip(keep).filter(|(_, keep)| *keep).map(|(item, _)| item).collect()))
+    }
+
+    //A lazy sequence is run first, its elements are then handed out like those of a list
+    pub(crate) fn items(&mut self, list : Datatype) -> Result<Vec<Datatype>, ProgramError> {
+        match list {
+            Datatype::List(items) => Ok(items),
+            Datatype::Sequence(sequence) => self.force(sequence),
+            _ => Err(ProgramError::ExpectedList),
+        }
+    }
+
+    //The workers count their steps in a total shared with the caller, so that the step limit holds for all
+    //of them together, and the caller goes on from that total
+    #[cfg(feature = "std")]
+    pub(crate) fn parallel<T : Send>(&mut self, items : &[Datatype], run : impl Fn(&mut Interpreter, Datatype) -> Result<T, ProgramError> + Sync) -> Result<Vec<T>, ProgramError> {
+        use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::thread;
+
+        if self.shared_steps.is_some() {
+            return self.sequential(items, run);
+        }
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get()).min(items.len());
+        self.spend(threads as u64)?;
+        let steps = Arc::new(AtomicU64::new(self.steps));
+
+        let next = AtomicUsize::new(0);
+        //Elements after the first one that failed do not need to be run
+        let failed = AtomicUsize::new(usize::MAX);
+
+        //Each worker takes the next element not yet taken, and gives back its results with their positions
+        let mut results : Vec<(usize, Result<T, ProgramError>)> = thread::scope(|scope| {
+            let workers : Vec<_> = (0..threads)
+                .map(|_| {
+                    let mut worker = self.worker();
+                    worker.shared_steps = Some(Arc::clone(&steps));
+                    worker.reported_steps = worker.steps;
+                    let (next, failed, run) = (&next, &failed, &run);
+                    scope.spawn(move || {
+                        let mut results = Vec::new();
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let item = match items.get(index) {
+                                Some(item) if index < failed.load(Ordering::Relaxed) => item,
+                                _ => break,
+                            };
+                            worker.stack.clear();
+                            let result = worker.copy(item).and_then(|item| run(&mut worker, item));
+                            if result.is_err() {
+                                failed.fetch_min(index, Ordering::Relaxed);
+                            }
+                            results.push((index, result));
+                        }
+                        results
+                    })
+                })
+                .collect();
+            workers.into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+                .collect()
+        });
+
+        self.steps = steps.load(Ordering::Relaxed);
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn parallel<T>(&mut self, items : &[Datatype], run : impl Fn(&mut Interpreter, Datatype) -> Result<T, ProgramError>) -> Result<Vec<T>, ProgramError> {
+        self.sequential(items, run)
+    }
+
+    //Without threads, or inside a worker, the elements are run one after the other on a single worker with the
+    //same restrictions. Its steps are counted in the same total as those of the caller
+    fn sequential<T>(&mut self, items : &[Datatype], run : impl Fn(&mut Interpreter, Datatype) -> Result<T, ProgramError>) -> Result<Vec<T>, ProgramError> {
+        self.spend(1)?;
+        let mut worker = self.worker();
+        #[cfg(feature = "std")]
+        {
+            worker.shared_steps = self.shared_steps.clone();
+            worker.reported_steps = self.reported_steps;
+        }
+        let results = items.iter()
+            .map(|item| {
+                worker.stack.clear();
+                worker.copy(item).and_then(|item| run(&mut worker, item))
+            })
+            .collect();
+        worker.total_steps();
+        self.steps = worker.steps;
+        #[cfg(feature = "std")]
+        {
+            self.reported_steps = worker.reported_steps;
+        }
+        results
+    }
+}
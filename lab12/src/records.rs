@@ -0,0 +1,156 @@
+//Records: `'point [ x y ] struct` defines the constructor `point` ( x y -- point ) and the accessors
+//`point.x` and `point.y`. A type can define the operators `+ - * / ==` for its records as functions named
+//like `point.+`, which take over from the builtin when either of the two operands is one of its records,
+//`!=` being the opposite of `==`. `dedup` and the literal patterns of `case` compare with that `==` too,
+//while `sort` orders records by tag and fields whatever `==` says, as there is no overload for ordering.
+//A function `point.show` ( point -- string ) gives the printed text. Records print as the literal
+//`( 1 2 'point [ x y ] record )`, which reads back without the struct being defined
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::words::not;
+use crate::{charge, Datatype, Interpreter, ProgramError};
+
+impl Interpreter {
+    //Defines the constructor and accessors, in the namespace being imported like any function
+    pub fn define_struct(&mut self, name : Datatype, fields : Datatype) -> Result<(), ProgramError> {
+        let name = match name {
+            Datatype::Symbol(name) => name,
+            _ => return Err(ProgramError::InvalidOperation),
+        };
+        let fields = field_names(fields)?;
+        //The tag is the qualified name, so that the record prints as a call to its constructor from anywhere
+        let tag = match &self.namespace {
+            Some(namespace) => format!("{}.{}", namespace, name),
+            None => name.clone(),
+        };
+
+        let constructor = format!("'{} [ {} ] record", tag, fields.join(" "));
+        self.define(Datatype::Symbol(name.clone()), Datatype::Code(constructor, Vec::new()))?;
+        for field in fields {
+            let accessor = format!("'{} field", field);
+            self.define(Datatype::Symbol(format!("{}.{}", name, field)), Datatype::Code(accessor, Vec::new()))?;
+        }
+        Ok(())
+    }
+
+    //( v1 .. vn 'tag [ f1 .. fn ] -- record ), the last field gets the top of the stack
+    pub fn record(&mut self, tag : Datatype, fields : Datatype) -> Result<Datatype, ProgramError> {
+        let tag = match tag {
+            Datatype::Symbol(tag) => tag,
+            _ => return Err(ProgramError::InvalidOperation),
+        };
+        let fields = field_names(fields)?;
+        if self.stack.len() < fields.len() {
+            return Err(ProgramError::StackEmpty);
+        }
+        let values = self.stack.split_off(self.stack.len() - fields.len());
+        for value in &values {
+            charge(&mut self.steps, self.limits, value, 1)?;
+        }
+        Ok(Datatype::Record(tag, fields.into_iter().zip(values).collect()))
+    }
+
+    //The function a record type defines for an operator, when one of the two top values is such a record
+    fn overload(&self, operator : &str) -> Option<String> {
+        let operator = match operator {
+            "+" | "-" | "*" | "/" | "==" => operator,
+            "!=" => "==",
+            _ => return None,
+        };
+        let operands = &self.stack[self.stack.len().checked_sub(2)?..];
+        operands.iter().find_map(|operand| self.defined(operand, operator))
+    }
+
+    pub(crate) fn defined(&self, operand : &Datatype, operator : &str) -> Option<String> {
+        match operand {
+            Datatype::Record(tag, _) => Some(format!("{}.{}", tag, operator)).filter(|name| self.functions.contains_key(name)),
+            _ => None,
+        }
+    }
+
+    //Whether `a b ==` gives True, for the words comparing values for equality without running `==`
+    pub(crate) fn equal(&mut self, a : &Datatype, b : &Datatype) -> Result<bool, ProgramError> {
+        let name = match self.defined(a, "==").or_else(|| self.defined(b, "==")) {
+            Some(name) => name,
+            None => return Ok(a == b),
+        };
+        let (a, b) = (self.copy(a)?, self.copy(b)?);
+        self.stack.push(a);
+        self.stack.push(b);
+        self.call(&name)?;
+        match self.pop()? {
+            Datatype::Boolean(equal) => Ok(equal),
+            _ => Err(ProgramError::ExpectedBool),
+        }
+    }
+
+    //Runs the operator's overload if there is one, returns false when the builtin should run instead
+    pub(crate) fn run_overload(&mut self, operator : &str) -> Result<bool, ProgramError> {
+        let name = match self.overload(operator) {
+            Some(name) => name,
+            None => return Ok(false),
+        };
+        self.call(&name)?;
+        if operator == "!=" {
+            let equal = self.pop()?;
+            self.stack.push(not(equal)?);
+        }
+        Ok(true)
+    }
+
+    //The text of a record whose type defines `show`. It runs on a pure interpreter sharing the functions, so
+    //printing changes nothing, and the record prints as usual when `show` fails or does not give a string
+    pub(crate) fn show(&self, value : &Datatype) -> Option<String> {
+        let name = self.defined(value, "show")?;
+        let mut worker = Interpreter::empty();
+        worker.functions = Arc::clone(&self.functions);
+        worker.limits = self.limits;
+        worker.precision = self.precision;
+        worker.pure = true;
+        worker.stack.push(value.clone());
+        match (worker.call(&name), worker.stack.as_slice()) {
+            (Ok(()), [Datatype::String(text)]) => Some(text.clone()),
+            _ => None,
+        }
+    }
+}
+
+//( record 'name -- value )
+pub fn field(name : Datatype, record : Datatype) -> Result<Datatype, ProgramError> {
+    match (record, name) {
+        (Datatype::Record(_, fields), Datatype::Symbol(name)) => fields.into_iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value)
+            .ok_or(ProgramError::NoSuchField),
+        (Datatype::Record(..), _) => Err(ProgramError::InvalidOperation),
+        _ => Err(ProgramError::ExpectedRecord),
+    }
+}
+
+//The record `( v1 .. vn 'tag [ f1 .. fn ] record )`, from the values before the fields
+pub(crate) fn read(mut values : Vec<Datatype>, fields : Datatype) -> Result<Datatype, ProgramError> {
+    let tag = match values.pop() {
+        Some(Datatype::Symbol(tag)) => tag,
+        _ => return Err(ProgramError::InvalidOperation),
+    };
+    let fields = field_names(fields)?;
+    if fields.len() != values.len() {
+        return Err(ProgramError::InvalidOperation);
+    }
+    Ok(Datatype::Record(tag, fields.into_iter().zip(values).collect()))
+}
+
+fn field_names(fields : Datatype) -> Result<Vec<String>, ProgramError> {
+    match fields {
+        Datatype::List(fields) => fields.into_iter()
+            .map(|field| match field {
+                Datatype::Symbol(name) => Ok(name),
+                _ => Err(ProgramError::InvalidOperation),
+            })
+            .collect(),
+        _ => Err(ProgramError::ExpectedList),
+    }
+}
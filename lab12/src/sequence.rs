@@ -0,0 +1,127 @@
+//Lazy sequences: `iterate` starts an endless sequence, `map`, `filter`, `take` and `takeWhile` add steps to it
+//without running anything, and `toList` runs the steps until a `take` or `takeWhile` ends the sequence. A
+//sequence prints as the words building it in parentheses, `( 1 iterate { 2 * } 5 take )`, which read back
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{nested, nested_quotation, Datatype, Interpreter, ProgramError};
+
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    //`seed iterate next` gives the seed, next applied to the seed, next applied to that and so on
+    pub seed: Box<Datatype>,
+    pub next: Box<Datatype>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Step {
+    Map(Datatype),
+    Filter(Datatype),
+    Take(usize),
+    TakeWhile(Datatype),
+}
+
+impl Sequence {
+    pub fn new(seed : Datatype, next : Datatype) -> Sequence {
+        Sequence { seed: Box::new(seed), next: Box::new(next), steps: Vec::new() }
+    }
+
+    pub fn then(mut self, step : Step) -> Datatype {
+        self.steps.push(step);
+        Datatype::Sequence(self)
+    }
+}
+
+//The rest of a sequence literal after `( seed iterate`: the next quotation and the steps up to the closing `)`
+pub(crate) fn read(seed : Datatype, tokens : &mut Vec<&str>, depth : usize) -> Result<Datatype, ProgramError> {
+    let next = nested_quotation(tokens, depth)?;
+    let mut sequence = Sequence::new(seed, next);
+    while let Some(token) = tokens.pop() {
+        let step = match token {
+            ")" => {
+                tokens.push(token);
+                break;
+            },
+            "map" => Step::Map(nested_quotation(tokens, depth)?),
+            "filter" => Step::Filter(nested_quotation(tokens, depth)?),
+            "takeWhile" => Step::TakeWhile(nested_quotation(tokens, depth)?),
+            _ => match (nested(token, tokens, depth)?, tokens.pop()) {
+                (Some(Datatype::Int(count)), Some("take")) => Step::Take(usize::try_from(count).map_err(|_| ProgramError::InvalidOperation)?),
+                _ => return Err(ProgramError::InvalidOperation),
+            },
+        };
+        sequence.steps.push(step);
+    }
+    Ok(Datatype::Sequence(sequence))
+}
+
+impl Interpreter {
+    //Elements are generated one at a time and only as far as the steps ask for, so an element after
+    //the last one taken is never computed
+    pub(crate) fn force(&mut self, sequence : Sequence) -> Result<Vec<Datatype>, ProgramError> {
+        //Without them the sequence never ends, and running it could only end at the limits or never
+        if !sequence.steps.iter().any(|step| matches!(step, Step::Take(_) | Step::TakeWhile(_))) {
+            return Err(ProgramError::UnboundedSequence);
+        }
+        let mut taken = vec![0; sequence.steps.len()];
+        let mut items = Vec::new();
+        let mut previous : Option<Datatype> = None;
+
+        loop {
+            let current = match previous {
+                None => self.copy(&sequence.seed)?,
+                Some(previous) => self.apply(&sequence.next, previous)?,
+            };
+            previous = Some(self.copy(&current)?);
+
+            let mut value = Some(current);
+            //Set when a `take` has let its last element through
+            let mut last = false;
+            for (index, step) in sequence.steps.iter().enumerate() {
+                let item = match value.take() {
+                    Some(item) => item,
+                    None => break,
+                };
+                value = match step {
+                    Step::Map(operation) => Some(self.apply(operation, item)?),
+                    Step::Filter(predicate) => if self.keeps(predicate, &item)? { Some(item) } else { None },
+                    Step::Take(count) => {
+                        if taken[index] == *count {
+                            return Ok(items);
+                        }
+                        taken[index] += 1;
+                        last |= taken[index] == *count;
+                        Some(item)
+                    },
+                    Step::TakeWhile(predicate) => if self.keeps(predicate, &item)? { Some(item) } else { return Ok(items) },
+                };
+            }
+
+            items.extend(value);
+            if last {
+                return Ok(items);
+            }
+        }
+    }
+
+    pub(crate) fn apply(&mut self, operation : &Datatype, item : Datatype) -> Result<Datatype, ProgramError> {
+        self.stack.push(item);
+        self.run_quotation(operation)?;
+        self.pop()
+    }
+
+    pub(crate) fn test(&mut self, predicate : &Datatype, item : Datatype) -> Result<bool, ProgramError> {
+        match self.apply(predicate, item)? {
+            Datatype::Boolean(value) => Ok(value),
+            _ => Err(ProgramError::ExpectedBool),
+        }
+    }
+
+    //Like test, on a copy of an item that is kept when the predicate holds
+    pub(crate) fn keeps(&mut self, predicate : &Datatype, item : &Datatype) -> Result<bool, ProgramError> {
+        let item = self.copy(item)?;
+        self.test(predicate, item)
+    }
+}
@@ -0,0 +1,140 @@
+//HTTP evaluation endpoint: `POST /eval` runs a program on a fresh sandboxed interpreter, which cannot import files, and answers with the final stack
+use std::io;
+use std::thread;
+
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use serde_json::{json, Map, Value};
+use tokio::net::TcpListener;
+
+use crate::{Datatype, Interpreter, Limits, ProgramError};
+
+//Stack a thread needs for every level of nested quotations and function calls, with room to spare in debug builds
+const FRAME_SIZE : usize = 64 << 10;
+
+pub const DEFAULT_LIMITS : Limits = Limits { steps: 1_000_000, stack: 10_000, depth: 64, length: 1_000_000 };
+
+#[derive(serde::Deserialize)]
+struct EvalRequest {
+    source: String,
+    //Values on the stack before the program runs, bottom first
+    #[serde(default)]
+    stack: Vec<Value>,
+}
+
+pub fn app(limits : Limits) -> Router {
+    Router::new()
+        .route("/eval", post(eval_handler))
+        .with_state(limits)
+}
+
+pub async fn serve(listener : TcpListener, limits : Limits) -> io::Result<()> {
+    axum::serve(listener, app(limits)).await
+}
+
+async fn eval_handler(State(limits): State<Limits>, Json(request): Json<EvalRequest>) -> (StatusCode, Json<Value>) {
+    let mut stack = Vec::new();
+    for (index, value) in request.stack.iter().enumerate() {
+        match from_json(value) {
+            Some(value) => stack.push(value),
+            None => return (StatusCode::BAD_REQUEST, Json(json!({
+                "error": "InvalidStack",
+                "message": format!("Stack value {} is not a bprog value", index),
+            }))),
+        }
+    }
+
+    //The interpreter is synchronous, so it runs on a thread of its own and leaves the runtime free for other
+    //requests. The thread gets a stack deep enough for the nesting the limits allow
+    let stack_size = limits.depth * FRAME_SIZE + (1 << 20);
+    let evaluation = tokio::task::spawn_blocking(move || {
+        thread::Builder::new()
+            .stack_size(stack_size)
+            .spawn(move || evaluate(&request.source, stack, limits))
+            .ok()?
+            .join()
+            .ok()
+    });
+    match evaluation.await {
+        Ok(Some(Ok(stack))) => (StatusCode::OK, Json(json!({ "stack": stack }))),
+        Ok(Some(Err((error, stack)))) => (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": format!("{:?}", error), "stack": stack }))),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": "InternalError" }))),
+    }
+}
+
+//Gives the final stack, or the error together with the stack as it was when the program failed
+fn evaluate(source : &str, stack : Vec<Datatype>, limits : Limits) -> Result<Vec<Value>, (ProgramError, Vec<Value>)> {
+    let mut interpreter = Interpreter::new();
+    interpreter.sandboxed = true;
+    interpreter.stack = stack;
+    interpreter.limits = Some(limits);
+
+    let result = interpreter.execute(source);
+    let stack = interpreter.stack.iter().map(to_json).collect();
+    match result {
+        Ok(()) => Ok(stack),
+        Err(e) => Err((e, stack)),
+    }
+}
+
+//Values are plain JSON where JSON has a matching type. The others are objects naming their kind:
+//{ "int": "<digits>" } beyond 64 bits, { "float": "NaN" } for non-finite floats, { "rational": "1/3" }, { "code": "1 +" },
+//{ "symbol": "name" },
+//{ "closure": "( 5 { | n | { n + } } exec )" }, { "sequence": "( 1 iterate { 1 + } )" } and { "record": "( 1 2 'point [ x y ] record )" }
+pub fn to_json(value : &Datatype) -> Value {
+    match value {
+        Datatype::Int(value) => match i64::try_from(*value) {
+            Ok(value) => json!(value),
+            Err(_) => json!({ "int": value.to_string() }),
+        },
+        Datatype::Float(value) if value.is_finite() => json!(value),
+        Datatype::Float(value) => json!({ "float": value.to_string() }),
+        Datatype::Rational(..) => json!({ "rational": crate::format_stack_item(value.clone()) }),
+        Datatype::Boolean(value) => json!(value),
+        Datatype::String(value) => json!(value),
+        Datatype::List(items) => Value::Array(items.iter().map(to_json).collect()),
+        //Like sequences, closures are only ever sent back
+        Datatype::Code(_, env) if !env.is_empty() => json!({ "closure": crate::format_stack_item(value.clone()) }),
+        Datatype::Code(code, _) => json!({ "code": code }),
+        Datatype::Symbol(name) => json!({ "symbol": name }),
+        //Only ever sent back, a sequence can be built from the source instead of passed in
+        Datatype::Sequence(_) => json!({ "sequence": crate::format_stack_item(value.clone()) }),
+        //Sent back as the call to its constructor, which only exists in the program that defined it
+        Datatype::Record(..) => json!({ "record": crate::format_stack_item(value.clone()) }),
+    }
+}
+
+pub fn from_json(value : &Value) -> Option<Datatype> {
+    match value {
+        Value::Number(number) => match number.as_i64() {
+            Some(value) => Some(Datatype::Int(value as i128)),
+            None => match number.as_u64() {
+                Some(value) => Some(Datatype::Int(value as i128)),
+                None => number.as_f64().map(Datatype::Float),
+            },
+        },
+        Value::Bool(value) => Some(Datatype::Boolean(*value)),
+        Value::String(value) => Some(Datatype::String(value.clone())),
+        Value::Array(items) => items.iter().map(from_json).collect::<Option<Vec<_>>>().map(Datatype::List),
+        Value::Object(object) => tagged(object),
+        Value::Null => None,
+    }
+}
+
+fn tagged(object : &Map<String, Value>) -> Option<Datatype> {
+    if object.len() != 1 {
+        return None;
+    }
+    let (kind, value) = object.iter().next()?;
+    let text = value.as_str()?;
+    match kind.as_str() {
+        "int" => text.parse().ok().map(Datatype::Int),
+        "float" => text.parse().ok().map(Datatype::Float),
+        "rational" => match crate::datatype(text, &mut Vec::new()) {
+            Ok(Some(value @ (Datatype::Rational(..) | Datatype::Int(_)))) => Some(value),
+            _ => None,
+        },
+        "code" => Some(Datatype::Code(text.to_string(), Vec::new())),
+        "symbol" => Some(Datatype::Symbol(text.to_string())),
+        _ => None,
+    }
+}
@@ -0,0 +1,176 @@
+//Saving and restoring the state of an interpreter: the stack, the local names, the functions with the
+//values they captured, the modules already imported and the float precision. Snapshots are JSON objects
+//{ "format": "bprog-snapshot", "version": 1, ... }, with every value tagged with its kind so that it reads
+//back exactly, e.g. { "int": "12" }, { "float": "NaN" }, { "code": "n +", "env": [["n", { "int": "5" }]] }
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde_json::{json, Map, Value};
+
+use crate::math::rational;
+use crate::sequence::{Sequence, Step};
+use crate::{Datatype, Env, Function, Interpreter, ProgramError};
+
+const FORMAT : &str = "bprog-snapshot";
+pub const SNAPSHOT_VERSION : u64 = 1;
+
+impl Interpreter {
+    pub fn snapshot(&self) -> String {
+        let functions : Vec<Value> = self.functions.iter()
+            .map(|(name, function)| json!({
+                "name": name,
+                "namespace": function.namespace,
+                "body": function.body,
+                "env": env_to_json(&function.env),
+            }))
+            .collect();
+        let mut imported : Vec<String> = self.imported.iter().map(|file| file.to_string_lossy().into_owned()).collect();
+        imported.sort();
+
+        let snapshot = json!({
+            "format": FORMAT,
+            "version": SNAPSHOT_VERSION,
+            "precision": self.precision,
+            "stack": self.stack.iter().map(value_to_json).collect::<Vec<_>>(),
+            "locals": self.locals.iter().map(env_to_json).collect::<Vec<_>>(),
+            "functions": functions,
+            "imported": imported,
+        });
+        serde_json::to_string_pretty(&snapshot).unwrap_or_default()
+    }
+
+    //Replaces the state of the interpreter with the one saved, which is left as it was when the snapshot
+    //cannot be read
+    pub fn restore(&mut self, snapshot : &str) -> Result<(), ProgramError> {
+        let snapshot : Value = serde_json::from_str(snapshot).map_err(|_| ProgramError::InvalidSnapshot)?;
+        if snapshot["format"] != FORMAT {
+            return Err(ProgramError::InvalidSnapshot);
+        }
+        if snapshot["version"].as_u64() != Some(SNAPSHOT_VERSION) {
+            return Err(ProgramError::UnsupportedSnapshotVersion);
+        }
+
+        let precision = match &snapshot["precision"] {
+            Value::Null => None,
+            precision => Some(precision.as_u64().filter(|digits| *digits > 0).ok_or(ProgramError::InvalidSnapshot)? as usize),
+        };
+        let stack = array(&snapshot["stack"])?.iter().map(value_from_json).collect::<Result<Vec<_>, _>>()?;
+        let locals = array(&snapshot["locals"])?.iter().map(env_from_json).collect::<Result<Vec<_>, _>>()?;
+        let mut functions = Vec::new();
+        for function in array(&snapshot["functions"])? {
+            let name = function["name"].as_str().ok_or(ProgramError::InvalidSnapshot)?;
+            let body = function["body"].as_str().ok_or(ProgramError::InvalidSnapshot)?;
+            let namespace = match &function["namespace"] {
+                Value::Null => None,
+                namespace => Some(namespace.as_str().ok_or(ProgramError::InvalidSnapshot)?.to_string()),
+            };
+            let env = env_from_json(&function["env"])?;
+            functions.push((name.to_string(), Function { body: body.to_string(), namespace, env, native: None }));
+        }
+        let imported = array(&snapshot["imported"])?.iter()
+            .map(|file| file.as_str().map(PathBuf::from).ok_or(ProgramError::InvalidSnapshot))
+            .collect::<Result<_, _>>()?;
+
+        self.stack = stack;
+        self.locals = locals;
+        self.functions = Arc::new(functions.into_iter().collect());
+        self.imported = imported;
+        self.precision = precision;
+        self.namespace = None;
+        Ok(())
+    }
+}
+
+fn array(value : &Value) -> Result<&Vec<Value>, ProgramError> {
+    value.as_array().ok_or(ProgramError::InvalidSnapshot)
+}
+
+fn env_to_json(env : &Env) -> Value {
+    Value::Array(env.iter().map(|(name, value)| json!([name, value_to_json(value)])).collect())
+}
+
+fn env_from_json(env : &Value) -> Result<Env, ProgramError> {
+    array(env)?.iter()
+        .map(|binding| match binding.as_array().map(Vec::as_slice) {
+            Some([Value::String(name), value]) => Ok((name.clone(), value_from_json(value)?)),
+            _ => Err(ProgramError::InvalidSnapshot),
+        })
+        .collect()
+}
+
+fn value_to_json(value : &Datatype) -> Value {
+    match value {
+        //Ints are written as strings, JSON numbers lose precision beyond 53 bits in many readers
+        Datatype::Int(value) => json!({ "int": value.to_string() }),
+        Datatype::Float(value) => json!({ "float": format!("{:?}", value) }),
+        Datatype::Rational(n, d) => json!({ "rational": [n.to_string(), d.to_string()] }),
+        Datatype::Boolean(value) => json!({ "bool": value }),
+        Datatype::String(value) => json!({ "string": value }),
+        Datatype::List(items) => json!({ "list": items.iter().map(value_to_json).collect::<Vec<_>>() }),
+        Datatype::Code(code, env) => json!({ "code": code, "env": env_to_json(env) }),
+        Datatype::Symbol(name) => json!({ "symbol": name }),
+        Datatype::Sequence(sequence) => {
+            let steps : Vec<Value> = sequence.steps.iter()
+                .map(|step| match step {
+                    Step::Map(operation) => json!({ "map": value_to_json(operation) }),
+                    Step::Filter(predicate) => json!({ "filter": value_to_json(predicate) }),
+                    Step::Take(count) => json!({ "take": count }),
+                    Step::TakeWhile(predicate) => json!({ "takeWhile": value_to_json(predicate) }),
+                })
+                .collect();
+            json!({ "sequence": { "seed": value_to_json(&sequence.seed), "next": value_to_json(&sequence.next), "steps": steps } })
+        },
+        Datatype::Record(tag, fields) => json!({ "record": { "tag": tag, "fields": env_to_json(fields) } }),
+    }
+}
+
+fn value_from_json(value : &Value) -> Result<Datatype, ProgramError> {
+    let object = value.as_object().ok_or(ProgramError::InvalidSnapshot)?;
+    let text = |key : &str| object.get(key).and_then(Value::as_str).ok_or(ProgramError::InvalidSnapshot);
+    let number = |value : &Value| value.as_str().and_then(|value| value.parse().ok()).ok_or(ProgramError::InvalidSnapshot);
+
+    let kind = object.keys().find(|key| *key != "env").ok_or(ProgramError::InvalidSnapshot)?;
+    match kind.as_str() {
+        "int" => number(&object["int"]).map(Datatype::Int),
+        "float" => text("float")?.parse().map(Datatype::Float).map_err(|_| ProgramError::InvalidSnapshot),
+        "rational" => match array(&object["rational"])?.as_slice() {
+            [n, d] => rational(number(n)?, number(d)?).ok().flatten().ok_or(ProgramError::InvalidSnapshot),
+            _ => Err(ProgramError::InvalidSnapshot),
+        },
+        "bool" => object["bool"].as_bool().map(Datatype::Boolean).ok_or(ProgramError::InvalidSnapshot),
+        "string" => Ok(Datatype::String(text("string")?.to_string())),
+        "list" => array(&object["list"])?.iter().map(value_from_json).collect::<Result<_, _>>().map(Datatype::List),
+        "code" => Ok(Datatype::Code(text("code")?.to_string(), env_from_json(&object["env"])?)),
+        "symbol" => Ok(Datatype::Symbol(text("symbol")?.to_string())),
+        "sequence" => sequence_from_json(&object["sequence"]),
+        "record" => match object["record"]["tag"].as_str() {
+            Some(tag) => Ok(Datatype::Record(tag.to_string(), env_from_json(&object["record"]["fields"])?)),
+            None => Err(ProgramError::InvalidSnapshot),
+        },
+        _ => Err(ProgramError::InvalidSnapshot),
+    }
+}
+
+fn sequence_from_json(sequence : &Value) -> Result<Datatype, ProgramError> {
+    let mut steps = Vec::new();
+    for step in array(&sequence["steps"])? {
+        let (kind, value) = step.as_object().and_then(single).ok_or(ProgramError::InvalidSnapshot)?;
+        steps.push(match kind.as_str() {
+            "map" => Step::Map(value_from_json(value)?),
+            "filter" => Step::Filter(value_from_json(value)?),
+            "take" => Step::Take(value.as_u64().ok_or(ProgramError::InvalidSnapshot)? as usize),
+            "takeWhile" => Step::TakeWhile(value_from_json(value)?),
+            _ => return Err(ProgramError::InvalidSnapshot),
+        });
+    }
+    let mut sequence = Sequence::new(value_from_json(&sequence["seed"])?, value_from_json(&sequence["next"])?);
+    sequence.steps = steps;
+    Ok(Datatype::Sequence(sequence))
+}
+
+fn single(object : &Map<String, Value>) -> Option<(&String, &Value)> {
+    match object.len() {
+        1 => object.iter().next(),
+        _ => None,
+    }
+}
@@ -0,0 +1,136 @@
+//Execution tracing (--trace) and profiling (--profile) for the interpreter.
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::{format_stack_item, Datatype};
+
+//Events kept for the Chrome trace, later ones are only counted so that long programs do not fill the memory.
+//The per-word statistics cover every word
+pub const MAX_EVENTS : usize = 100_000;
+
+struct WordStats {
+    calls: u64,
+    total: Duration,
+}
+
+//One executed word, kept for the Chrome trace-event output
+struct TraceEvent {
+    word: String,
+    start: Duration,
+    duration: Duration,
+}
+
+//Returned by `enter` and handed back to `exit` once the word has run
+pub struct Frame {
+    started: Instant,
+    before: String,
+}
+
+pub struct Tracer {
+    trace: bool,
+    profile: bool,
+    active: Vec<String>,
+    origin: Instant,
+    stats: HashMap<String, WordStats>,
+    events: Vec<TraceEvent>,
+    //Events left out of the trace after MAX_EVENTS
+    dropped: u64,
+}
+
+impl Tracer {
+    pub fn new(trace : bool, profile : bool) -> Tracer {
+        Tracer {
+            trace,
+            profile,
+            active: Vec::new(),
+            origin: Instant::now(),
+            stats: HashMap::new(),
+            events: Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    //None when neither tracing nor profiling, so that running words costs nothing extra then
+    pub fn enter(&mut self, word : &str, stack : &[Datatype]) -> Option<Frame> {
+        if !self.trace && !self.profile {
+            return None;
+        }
+        let before = if self.trace { format_stack(stack) } else { String::new() };
+        self.active.push(word.to_string());
+        Some(Frame { started: Instant::now(), before })
+    }
+
+    pub fn exit(&mut self, word : &str, frame : Option<Frame>, stack : &[Datatype]) {
+        let frame = match frame {
+            Some(frame) => frame,
+            None => return,
+        };
+        let elapsed = frame.started.elapsed();
+        self.active.pop();
+
+        if self.trace {
+            eprintln!("trace: {}{} {} -> {}", "  ".repeat(self.active.len()), word, frame.before, format_stack(stack));
+        }
+
+        if self.profile {
+            let stats = self.stats.entry(word.to_string()).or_insert(WordStats { calls: 0, total: Duration::ZERO });
+            stats.calls += 1;
+            //Nested calls of the same word (e.g. map inside map) are already part of the outer call's time
+            if !self.active.iter().any(|active| active == word) {
+                stats.total += elapsed;
+            }
+            if self.events.len() < MAX_EVENTS {
+                self.events.push(TraceEvent {
+                    word: word.to_string(),
+                    start: frame.started.duration_since(self.origin),
+                    duration: elapsed,
+                });
+            } else {
+                self.dropped += 1;
+            }
+        }
+    }
+
+    //Per-word call counts and cumulative time, slowest words first
+    pub fn report(&self) -> String {
+        let mut words : Vec<(&String, &WordStats)> = self.stats.iter().collect();
+        words.sort_by(|a, b| b.1.total.cmp(&a.1.total).then(a.0.cmp(b.0)));
+
+        let mut report = String::new();
+        writeln!(report, "{:<16} {:>10} {:>14}", "word", "calls", "total (us)").unwrap();
+        for (word, stats) in words {
+            writeln!(report, "{:<16} {:>10} {:>14.3}", word, stats.calls, stats.total.as_secs_f64() * 1e6).unwrap();
+        }
+        if self.dropped > 0 {
+            writeln!(report, "{} more words are counted above but left out of the trace, which keeps the first {}", self.dropped, MAX_EVENTS).unwrap();
+        }
+        report
+    }
+
+    //Profile in the Chrome trace-event format, loadable in chrome://tracing or Perfetto. The number of events
+    //left out is given as `droppedEvents` in its metadata
+    pub fn chrome_trace(&self) -> String {
+        let events : Vec<_> = self.events.iter()
+            .map(|event| json!({
+                "name": event.word,
+                "cat": "word",
+                "ph": "X",
+                "ts": event.start.as_secs_f64() * 1e6,
+                "dur": event.duration.as_secs_f64() * 1e6,
+                "pid": 1,
+                "tid": 1,
+            }))
+            .collect();
+        let mut json = json!({ "traceEvents": events, "displayTimeUnit": "ms", "otherData": { "droppedEvents": self.dropped } }).to_string();
+        json.push('\n');
+        json
+    }
+}
+
+fn format_stack(stack : &[Datatype]) -> String {
+    let items : Vec<String> = stack.iter().map(|item| format_stack_item(item.clone())).collect();
+    format!("[ {} ]", items.join(" "))
+}
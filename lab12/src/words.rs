@@ -0,0 +1,145 @@
+//The builtins on lists, strings, booleans and types that only need the values they pop, shared by the
+//interpreter and compiled programs. The top of the stack is the first argument
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::lexer::tokenize;
+use crate::sequence::Step;
+use crate::{Datatype, ProgramError};
+
+pub fn empty(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::List(list) => Ok(Datatype::Boolean(list.is_empty())),
+        _ => Err(ProgramError::InvalidOperation),
+    }
+}
+
+pub fn append(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    match (a, b) {
+        (Datatype::List(mut list), Datatype::List(mut list2)) => {
+            list2.append(&mut list);
+            Ok(Datatype::List(list2))
+        },
+        _ => Err(ProgramError::InvalidOperation),
+    }
+}
+
+pub fn cons(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    match (a, b) {
+        (Datatype::List(mut list), item) => {
+            list.insert(0, item);
+            Ok(Datatype::List(list))
+        },
+        _ => Err(ProgramError::InvalidOperation),
+    }
+}
+
+//The first a elements of a list, or a sequence ending after its first a elements
+pub fn take(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    let count = match a {
+        Datatype::Int(count) => usize::try_from(count).map_err(|_| ProgramError::InvalidOperation)?,
+        _ => return Err(ProgramError::ExpectedNumber),
+    };
+    match b {
+        Datatype::List(list) => Ok(Datatype::List(list.into_iter().take(count).collect())),
+        Datatype::Sequence(sequence) => Ok(sequence.then(Step::Take(count))),
+        _ => Err(ProgramError::ExpectedList),
+    }
+}
+
+pub fn tail(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::List(mut list) => {
+            if list.is_empty() {
+                Err(ProgramError::InvalidOperation)
+            } else {
+                list.remove(0);
+                Ok(Datatype::List(list))
+            }
+        },
+        _ => Err(ProgramError::InvalidOperation),
+    }
+}
+
+pub fn head(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::List(list) => list.into_iter().next().ok_or(ProgramError::InvalidOperation),
+        _ => Err(ProgramError::InvalidOperation),
+    }
+}
+
+pub fn length(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::List(list) => Ok(Datatype::Int(list.len() as i128)),
+        Datatype::String(string) => Ok(Datatype::Int(string.chars().count() as i128)),
+        Datatype::Code(string, _) => Ok(Datatype::Int(tokenize(&string).len() as i128)),
+        _ => Err(ProgramError::InvalidOperation),
+    }
+}
+
+pub fn parse_integer(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::String(value) => {
+            match value.parse::<i128>() {
+                Ok(value) => Ok(Datatype::Int(value)),
+                Err(_) => Err(ProgramError::InvalidOperation),
+            }
+        },
+        _ => Err(ProgramError::InvalidOperation),
+    }
+}
+
+pub fn parse_float(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::String(value) => {
+            match value.parse::<f64>() {
+                Ok(value) => Ok(Datatype::Float(value)),
+                Err(_) => Err(ProgramError::InvalidOperation),
+            }
+        },
+        _ => Err(ProgramError::InvalidOperation),
+    }
+}
+
+pub fn split_words(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::String(value) => {
+            let words : Vec<Datatype> = value.split(' ').map(|x| Datatype::String(x.to_string())).collect();
+            Ok(Datatype::List(words))
+        },
+        _ => Err(ProgramError::ExpectedString),
+    }
+}
+
+pub fn and(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    match (a, b) {
+        (Datatype::Boolean(a), Datatype::Boolean(b)) => Ok(Datatype::Boolean(a && b)),
+        _ => Err(ProgramError::ExpectedBool),
+    }
+}
+
+pub fn or(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    match (a, b) {
+        (Datatype::Boolean(a), Datatype::Boolean(b)) => Ok(Datatype::Boolean(a || b)),
+        _ => Err(ProgramError::ExpectedBool),
+    }
+}
+
+pub fn not(a : Datatype) -> Result<Datatype, ProgramError> {
+    match a {
+        Datatype::Boolean(a) => Ok(Datatype::Boolean(!a)),
+        _ => Err(ProgramError::InvalidOperation),
+    }
+}
+
+pub fn equal(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    Ok(Datatype::Boolean(a == b))
+}
+
+pub fn not_equal(a : Datatype, b : Datatype) -> Result<Datatype, ProgramError> {
+    Ok(Datatype::Boolean(a != b))
+}
+
+pub fn type_of(a : Datatype) -> Result<Datatype, ProgramError> {
+    Ok(Datatype::String(a.type_name().to_string()))
+}
@@ -0,0 +1,26 @@
+//The example programs and what the interpreter prints for them, shared by the golden files and the compiler tests
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lab12::Interpreter;
+
+//Every program in examples/, in order of name
+pub fn programs() -> Vec<PathBuf> {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut programs : Vec<PathBuf> = fs::read_dir(examples).unwrap()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "bprog"))
+        .collect();
+    programs.sort();
+    programs
+}
+
+//What `lab12 program.bprog` prints
+pub fn run(program : &Path) -> String {
+    let mut interpreter = Interpreter::new();
+    match interpreter.run_file(&program.to_string_lossy()) {
+        Ok(value) => format!("{}\n", interpreter.format(value)),
+        Err(e) => format!("Error: {:?}\n", e),
+    }
+}
@@ -0,0 +1,207 @@
+//Compiles every program in examples/ to a Rust binary, builds them and checks that they print what the interpreter
+//prints, wherever they are run from
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use common::{programs, run};
+use lab12::builtins::BUILTINS;
+use lab12::compile::{compile, Pattern, Quotation};
+use lab12::{Datatype, Interpreter, ProgramError};
+
+const PROJECT : &str = concat!(env!("CARGO_TARGET_TMPDIR"), "/compiled");
+
+//A crate depending on this one with a binary for every compiled program, built once for all of them
+fn write_project(programs : &[PathBuf]) -> Vec<String> {
+    let project = Path::new(PROJECT);
+    fs::create_dir_all(project.join("src/bin")).unwrap();
+    fs::write(project.join("Cargo.toml"), format!(
+        "[package]\nname = \"compiled\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nlab12 = {{ path = {:?} }}\n\n[workspace]\n",
+        env!("CARGO_MANIFEST_DIR"))).unwrap();
+    //The versions this crate is built with, so that no other ones have to be fetched
+    if let Ok(lock) = fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.lock")) {
+        fs::write(project.join("Cargo.lock"), lock).unwrap();
+    }
+
+    programs.iter()
+        .map(|program| {
+            let name = program.file_stem().unwrap().to_string_lossy().into_owned();
+            let source = fs::read_to_string(program).unwrap();
+            let rust = compile(&source, program.parent()).unwrap_or_else(|e| panic!("{} cannot be compiled: {:?}", name, e));
+            fs::write(project.join("src/bin").join(format!("{}.rs", name)), rust).unwrap();
+            name
+        })
+        .collect()
+}
+
+#[test]
+fn compiled_examples_print_what_the_interpreter_prints() {
+    let programs = programs();
+    let names = write_project(&programs);
+
+    let manifest = format!("{}/Cargo.toml", PROJECT);
+    let build = Command::new(env!("CARGO"))
+        .args(["build", "--quiet", "--bins", "--manifest-path", &manifest])
+        .env("CARGO_TARGET_DIR", concat!(env!("CARGO_TARGET_TMPDIR"), "/compiled-target"))
+        .output()
+        .expect("cargo could not be started");
+    assert!(build.status.success(), "the compiled programs do not build:\n{}", String::from_utf8_lossy(&build.stderr));
+
+    for (program, name) in programs.iter().zip(&names) {
+        let binary = Path::new(env!("CARGO_TARGET_TMPDIR")).join("compiled-target/debug").join(name);
+        //Away from the examples, so that imports can only be found in the binary
+        let output = Command::new(&binary).current_dir(env!("CARGO_TARGET_TMPDIR")).output().unwrap_or_else(|e| panic!("{} could not be started: {}", binary.display(), e));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), run(program), "compiled {} prints something else", name);
+    }
+}
+
+#[test]
+fn builtins_become_direct_calls() {
+    let rust = compile("1 2 + [ 3 ] head max swap", None).unwrap();
+    assert!(rust.contains(r#"vm.push_value(Datatype::Int(1i128));"#), "{}", rust);
+    assert!(rust.contains(r#"vm.operator("+", math::add)?;"#), "{}", rust);
+    assert!(rust.contains(r#"vm.unary(words::head)?;"#), "{}", rust);
+    assert!(rust.contains(r#"vm.method(Interpreter::max)?;"#), "{}", rust);
+
+    //Only the words taking a quotation need one after them
+    for builtin in BUILTINS {
+        let source = match builtin.name {
+            "if" => "if { 1 } { 2 }".to_string(),
+            "case" => "case { _ { } }".to_string(),
+            "import" => "import lib.bprog".to_string(),
+            "help" => "help dup".to_string(),
+            name if builtin.effect.contains(&format!(") {}", name)) => format!("{} {{ 1 }}", name),
+            name => name.to_string(),
+        };
+        let rust = compile(&source, None).unwrap();
+        assert!(!rust.contains("call_word"), "{} is not compiled to a direct call:\n{}", builtin.name, rust);
+    }
+}
+
+#[test]
+fn quotations_become_rust_functions() {
+    let rust = compile("[ 1 2 ] map { 1 + } True if { [ 3 ] } 'inc", None).unwrap();
+    assert!(rust.contains(r#"vm.run_map(&QUOTATION_2)?;"#), "{}", rust);
+    assert!(rust.contains("fn function_1(vm : &mut Interpreter) -> Result<(), ProgramError> {\n    vm.push_value(Datatype::Int(1i128));\n    vm.operator(\"+\", math::add)?;\n"), "{}", rust);
+    assert!(rust.contains(r#"const QUOTATION_2 : Quotation = Quotation { value: || Datatype::Code("1 +".to_string(), vec![]), run: function_1 };"#), "{}", rust);
+    assert!(rust.contains(r#"run: |vm| vm.run_value(Datatype::Symbol("inc".to_string())) };"#), "{}", rust);
+}
+
+#[test]
+fn local_names_hide_builtins() {
+    let rust = compile("{ | length | length } exec 2 { | n | n length } fun", None).unwrap();
+    assert!(rust.contains(r#"vm.with_names(&["n"], |vm| {"#), "{}", rust);
+    assert!(rust.contains(r#"vm.call_word("n")?;"#), "{}", rust);
+    assert!(rust.contains(r#"vm.unary(words::length)?;"#), "{}", rust);
+
+    let rust = compile("[ 1 ] case { [ head | tail ] { head tail } }", None).unwrap();
+    assert!(rust.contains(r#"vm.call_word("head")?;"#), "{}", rust);
+    assert!(!rust.contains("words::head"), "{}", rust);
+}
+
+#[test]
+fn only_names_before_a_definition_become_symbols() {
+    let rust = compile("sq { dup * } fun point [ x y ] struct 3 sq", None).unwrap();
+    assert!(rust.contains(r#"vm.name_word("sq")?;"#), "{}", rust);
+    assert!(rust.contains(r#"vm.name_word("point")?;"#), "{}", rust);
+    assert!(rust.contains(r#"vm.call_word("sq")?;"#), "{}", rust);
+
+    let mut interpreter = Interpreter::new();
+    assert!(matches!(interpreter.call_word("dupp"), Err(ProgramError::UnknownWord)));
+}
+
+#[test]
+fn malformed_programs_do_not_compile() {
+    assert!(matches!(compile("[ 1 2", None), Err(ProgramError::IncompleteList)));
+    assert!(matches!(compile("1 map", None), Err(ProgramError::ExpectedQuotation)));
+    assert!(matches!(compile("1 case { foo }", None), Err(ProgramError::InvalidOperation)));
+}
+
+#[test]
+fn parenthesized_literals_become_rust_values() {
+    let rust = compile("( 1 'p [ x ] record ) ( 2 { | n | { n } } exec )", None).unwrap();
+    assert!(rust.contains(r#"vm.push_value(Datatype::Record("p".to_string(), vec![("x".to_string(), Datatype::Int(1i128))]));"#), "{}", rust);
+    assert!(rust.contains(r#"vm.push_value(Datatype::Code("n".to_string(), vec![("n".to_string(), Datatype::Int(2i128))]));"#), "{}", rust);
+}
+
+#[test]
+fn imported_modules_are_embedded_by_their_relative_path() {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let rust = compile(&fs::read_to_string(examples.join("modules.bprog")).unwrap(), Some(&examples)).unwrap();
+    assert!(rust.contains(r#"vm.add_module("lib/stats.bprog", "#), "{}", rust);
+    assert!(rust.contains(r#"vm.add_module("lib/squares.bprog", "squares { map { dup * } } fun\n");"#), "{}", rust);
+    assert!(!rust.contains(env!("CARGO_MANIFEST_DIR")), "{}", rust);
+}
+
+//What the compiler generates for the quotations `n *` and `2 *`
+fn times_n(vm : &mut Interpreter) -> Result<(), ProgramError> {
+    vm.call_word("n")?;
+    vm.operator("*", lab12::math::multiply)
+}
+
+fn twice(vm : &mut Interpreter) -> Result<(), ProgramError> {
+    vm.push_value(Datatype::Int(2));
+    vm.operator("*", lab12::math::multiply)
+}
+
+const TIMES_N : Quotation = Quotation { value: || Datatype::Code("n *".to_string(), vec![]), run: times_n };
+
+#[test]
+fn compiled_runtime_runs_like_the_interpreter() {
+    fn program(vm : &mut Interpreter) -> Result<(), ProgramError> {
+        vm.push_value(Datatype::Int(3));
+        vm.with_names(&["n"], |vm| {
+            vm.call_word("n")?;
+            vm.call_word("double")?;
+            vm.push_value(Datatype::List(vec![Datatype::Int(1), Datatype::Int(2)]));
+            vm.run_map(&TIMES_N)?;
+            vm.binary(lab12::words::cons)
+        })
+    }
+    let mut interpreter = Interpreter::new();
+    interpreter.execute("double { 2 * } fun").unwrap();
+    let value = interpreter.run_compiled(program).unwrap();
+    assert_eq!(interpreter.format(value), "[ 6 3 6 ]");
+}
+
+#[test]
+fn compiled_functions_are_called_by_the_interpreter() {
+    let mut interpreter = Interpreter::new();
+    interpreter.stack.push(Datatype::Symbol("twice".to_string()));
+    interpreter.define_compiled(Datatype::Code("2 *".to_string(), Vec::new()), twice).unwrap();
+    assert_eq!(interpreter.run("[ 1 2 ] map twice").map(|value| interpreter.format(value)).unwrap(), "[ 2 4 ]");
+    assert_eq!(interpreter.run("help twice").map(|value| interpreter.format(value)).unwrap(), "\" twice user function { 2 * } \"");
+}
+
+#[test]
+fn case_binds_the_parts_of_a_list() {
+    fn rest(vm : &mut Interpreter) -> Result<(), ProgramError> {
+        vm.call_word("t")
+    }
+    const REST : Quotation = Quotation { value: || Datatype::Code("t".to_string(), vec![]), run: rest };
+
+    let mut interpreter = Interpreter::new();
+    interpreter.stack.push(Datatype::List(vec![Datatype::Int(1), Datatype::Int(2)]));
+    interpreter.run_case(vec![(Pattern::Literal(Datatype::List(Vec::new())), &REST), (Pattern::HeadTail("h".to_string(), "t".to_string()), &REST)]).unwrap();
+    assert_eq!(interpreter.stack.len(), 1);
+    assert_eq!(interpreter.format(interpreter.stack[0].clone()), "[ 2 ]");
+
+    interpreter.stack = vec![Datatype::Int(1)];
+    assert!(matches!(interpreter.run_case(vec![(Pattern::Type("string".to_string()), &REST)]), Err(ProgramError::NoMatch)));
+}
+
+#[test]
+fn embedded_modules_are_imported_before_files() {
+    fn program(vm : &mut Interpreter) -> Result<(), ProgramError> {
+        vm.import("geometry.bprog")?;
+        vm.push_value(Datatype::Int(3));
+        vm.call_word("geometry.double")
+    }
+    let mut interpreter = Interpreter::new();
+    interpreter.search_path.push(Path::new(env!("CARGO_MANIFEST_DIR")).join("test-modules"));
+    interpreter.add_module("geometry.bprog", "double { 2 * } fun");
+    let value = interpreter.run_compiled(program).unwrap();
+    assert_eq!(interpreter.format(value), "6");
+}
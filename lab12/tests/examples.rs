@@ -0,0 +1,63 @@
+//Runs every program in examples/ and compares what it prints with the golden .out file next to it.
+//`cargo test --test examples -- --update` writes the golden files from the current output instead
+mod common;
+
+use std::env;
+use std::fs;
+use std::process;
+
+use common::{programs, run};
+
+fn main() {
+    let update = env::args().any(|arg| arg == "--update");
+
+    let mut failed = 0;
+    let programs = programs();
+    for program in &programs {
+        let name = program.file_name().unwrap().to_string_lossy();
+        let golden = program.with_extension("out");
+        let output = run(program);
+
+        if update {
+            fs::write(&golden, &output).unwrap();
+            println!("example {} ... updated", name);
+            continue;
+        }
+        match fs::read_to_string(&golden) {
+            Ok(expected) if expected == output => println!("example {} ... ok", name),
+            Ok(expected) => {
+                failed += 1;
+                println!("example {} ... FAILED\n{}", name, diff(&expected, &output));
+            },
+            Err(_) => {
+                failed += 1;
+                println!("example {} ... FAILED, {} is missing, run with --update to write it", name, golden.display());
+            },
+        }
+    }
+
+    println!("\n{} examples, {} failed", programs.len(), failed);
+    if failed > 0 {
+        process::exit(1);
+    }
+}
+
+//The lines that differ, expected ones marked with - and actual ones with +
+fn diff(expected : &str, actual : &str) -> String {
+    let expected : Vec<&str> = expected.lines().collect();
+    let actual : Vec<&str> = actual.lines().collect();
+
+    let mut text = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        let (old, new) = (expected.get(i), actual.get(i));
+        if old != new {
+            if let Some(line) = old {
+                text.push_str(&format!("  -{}\n", line));
+            }
+            if let Some(line) = new {
+                text.push_str(&format!("  +{}\n", line));
+            }
+        }
+    }
+    text
+}
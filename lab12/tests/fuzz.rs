@@ -0,0 +1,169 @@
+//Random programs and values thrown at the parser and the evaluator: nothing may panic, and whatever
+//is printed has to parse back to an equal value
+use lab12::builtins::BUILTINS;
+use lab12::compare::compare;
+use lab12::sequence::{Sequence, Step};
+use lab12::{format_source, format_stack_item, Datatype, Interpreter, Limits, ProgramError, MAX_NESTING};
+use proptest::prelude::*;
+
+//Small enough that endless loops and runaway recursion end quickly
+const LIMITS : Limits = Limits { steps: 2_000, stack: 200, depth: 24, length: 200 };
+
+fn token() -> impl Strategy<Value = String> {
+    let words : Vec<String> = BUILTINS.iter().map(|builtin| builtin.name.to_string()).collect();
+    prop_oneof![
+        4 => proptest::sample::select(words),
+        2 => proptest::sample::select(vec!["{", "}", "[", "]", "(", ")", "\"", "|", "_", "'", "'+", "f", "x", "True", "NaN", "-inf", "1/3", "-2/4", "\" a  \\\"b\\\" \"", "\"\\u{e9}\""]).prop_map(String::from),
+        2 => any::<i8>().prop_map(|value| value.to_string()),
+        1 => any::<i128>().prop_map(|value| value.to_string()),
+        1 => any::<f64>().prop_map(|value| format!("{:?}", value)),
+    ]
+}
+
+fn program() -> impl Strategy<Value = String> {
+    proptest::collection::vec(token(), 0..40).prop_map(|tokens| tokens.join(" "))
+}
+
+fn name() -> impl Strategy<Value = String> {
+    "[a-zA-Z+*/<>=!_.-][a-zA-Z0-9+*/<>=!_.-]{0,8}"
+}
+
+//Field names, which unlike other names may not read as a value inside `[ ]`
+fn field() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9_]{0,6}".prop_filter("a float", |name| name != "inf")
+}
+
+fn code() -> impl Strategy<Value = String> {
+    proptest::collection::vec(name(), 0..4).prop_map(|words| words.join(" "))
+}
+
+fn leaf() -> impl Strategy<Value = Datatype> {
+    prop_oneof![
+        any::<i128>().prop_map(Datatype::Int),
+        prop_oneof![any::<f64>(), Just(f64::NAN), Just(f64::INFINITY), Just(f64::NEG_INFINITY), Just(-0.0)].prop_map(Datatype::Float),
+        (any::<i64>(), 1..i64::MAX).prop_filter_map("not a rational", |(n, d)| lab12::math::rational(n as i128, d as i128).ok().flatten()),
+        any::<bool>().prop_map(Datatype::Boolean),
+        any::<String>().prop_map(Datatype::String),
+        code().prop_map(|code| Datatype::Code(code, Vec::new())),
+        name().prop_map(Datatype::Symbol),
+    ]
+}
+
+fn step(inner : BoxedStrategy<Datatype>) -> impl Strategy<Value = Step> {
+    prop_oneof![
+        inner.clone().prop_map(Step::Map),
+        inner.clone().prop_map(Step::Filter),
+        (0..100usize).prop_map(Step::Take),
+        inner.prop_map(Step::TakeWhile),
+    ]
+}
+
+//Lists, closures, records and sequences of other values
+fn value() -> impl Strategy<Value = Datatype> {
+    leaf().prop_recursive(3, 24, 4, |inner| prop_oneof![
+        proptest::collection::vec(inner.clone(), 0..4).prop_map(Datatype::List),
+        //Kept in order of name like the closures the interpreter makes
+        (code(), proptest::collection::btree_map(name(), inner.clone(), 1..3))
+            .prop_map(|(code, env)| Datatype::Code(code, env.into_iter().collect())),
+        (name(), proptest::collection::vec((field(), inner.clone()), 0..3)).prop_map(|(tag, fields)| Datatype::Record(tag, fields)),
+        (inner.clone(), inner.clone(), proptest::collection::vec(step(inner), 0..3)).prop_map(|(seed, next, steps)| {
+            Datatype::Sequence(Sequence { seed: Box::new(seed), next: Box::new(next), steps })
+        }),
+    ])
+}
+
+//A leaf inside up to a few more lists, closures, records and sequences than the parser reads, with their number
+fn deeply_nested() -> impl Strategy<Value = (Datatype, usize)> {
+    (leaf(), proptest::collection::vec(0..4u8, 0..MAX_NESTING + 8)).prop_map(|(leaf, wrappers)| {
+        let levels = wrappers.len();
+        let value = wrappers.into_iter().fold(leaf, |value, wrapper| match wrapper {
+            0 => Datatype::List(vec![value]),
+            1 => Datatype::Code("n".to_string(), vec![("n".to_string(), value)]),
+            2 => Datatype::Record("r".to_string(), vec![("f".to_string(), value)]),
+            _ => Datatype::Sequence(Sequence::new(value, Datatype::Int(1))),
+        });
+        (value, levels)
+    })
+}
+
+//Ints, floats and rationals around 2^53, where neighbouring ints round to the same float
+fn number_near_2_53() -> impl Strategy<Value = Datatype> {
+    let near = -4i128..4;
+    prop_oneof![
+        near.clone().prop_map(|k| Datatype::Int((1 << 53) + k)),
+        near.clone().prop_map(|k| Datatype::Float(((1i128 << 53) + k) as f64)),
+        (near, 2i128..5, 1i128..4).prop_filter_map("not a rational", |(k, d, r)| lab12::math::rational(((1 << 53) + k) * d + r % d, d).ok().flatten()),
+    ]
+}
+
+fn reparse(printed : &str) -> Datatype {
+    match Interpreter::new().run(printed) {
+        Ok(value) => value,
+        Err(e) => panic!("{} does not parse back: {:?}", printed, e),
+    }
+}
+
+#[test]
+fn lists_nest_as_deep_as_the_limit() {
+    let nested = |depth : usize| format!("{}{}", "[ ".repeat(depth), "] ".repeat(depth));
+    assert!(Interpreter::new().run(&nested(MAX_NESTING)).is_ok());
+    assert!(matches!(Interpreter::new().run(&nested(MAX_NESTING + 1)), Err(ProgramError::NestingTooDeep)));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn programs_never_panic(program in program()) {
+        let mut interpreter = Interpreter::new();
+        interpreter.limits = Some(LIMITS);
+        let _ = interpreter.execute(&program);
+
+        for value in &interpreter.stack {
+            let printed = format_stack_item(value.clone());
+            prop_assert_eq!(&reparse(&printed), value, "printed as {}", printed);
+        }
+    }
+
+    #[test]
+    fn printed_values_parse_back_equal(value in value()) {
+        let printed = format_stack_item(value.clone());
+        prop_assert_eq!(reparse(&printed), value, "printed as {}", printed);
+    }
+
+    //The brackets of quotations and the names of records count as levels too, so only the values well
+    //inside the limit have to read back, and only those past it have to be refused
+    #[test]
+    fn deep_values_read_back_or_are_refused((value, levels) in deeply_nested()) {
+        let printed = format_stack_item(value.clone());
+        match Interpreter::new().run(&printed) {
+            Ok(read) => {
+                prop_assert!(levels <= MAX_NESTING, "{} levels read back", levels);
+                prop_assert_eq!(read, value);
+            },
+            Err(ProgramError::NestingTooDeep) => prop_assert!(levels + 3 > MAX_NESTING, "{} levels refused", levels),
+            Err(e) => prop_assert!(false, "{} levels fail with {:?}", levels, e),
+        }
+    }
+
+    #[test]
+    fn numbers_are_totally_ordered(numbers in proptest::collection::vec(number_near_2_53(), 0..10)) {
+        for a in &numbers {
+            for b in &numbers {
+                prop_assert_eq!(compare(a, b), compare(b, a).reverse(), "{:?} {:?}", a, b);
+                for c in &numbers {
+                    if compare(a, b).is_le() && compare(b, c).is_le() {
+                        prop_assert!(compare(a, c).is_le(), "{:?} <= {:?} <= {:?}", a, b, c);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn formatting_never_panics_and_settles(program in program()) {
+        if let Ok(formatted) = format_source(&program) {
+            prop_assert_eq!(format_source(&formatted).ok(), Some(formatted));
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//Drives the bprog-lsp binary over stdio the way an editor would
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde_json::{json, Value};
+
+const URI : &str = "file:///tmp/example.bprog";
+const SOURCE : &str = "inc { 1 + } fun\n1 inc\n[ 1 2";
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Client {
+    fn start() -> Client {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_bprog-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("could not start bprog-lsp");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Client { child, stdin, stdout }
+    }
+
+    fn send(&mut self, message : Value) {
+        self.send_body(message.to_string().as_bytes());
+    }
+
+    fn send_body(&mut self, body : &[u8]) {
+        write!(self.stdin, "Content-Length: {}\r\n\r\n", body.len()).unwrap();
+        self.stdin.write_all(body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).unwrap();
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length:") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn request(&mut self, id : i64, method : &str, params : Value) -> Value {
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        let response = self.receive();
+        assert_eq!(response["id"], id);
+        response
+    }
+
+    //Initializes and opens SOURCE, returning the diagnostics published for it
+    fn open() -> (Client, Value) {
+        let mut client = Client::start();
+        let response = client.request(1, "initialize", json!({ "capabilities": {} }));
+        assert_eq!(response["result"]["capabilities"]["hoverProvider"], true);
+        client.send(json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }));
+        client.send(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": URI, "languageId": "bprog", "version": 1, "text": SOURCE } },
+        }));
+        let diagnostics = client.receive();
+        (client, diagnostics)
+    }
+
+    fn close(mut self) -> bool {
+        self.request(99, "shutdown", Value::Null);
+        self.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+        self.child.wait().unwrap().success()
+    }
+}
+
+fn position(line : u64, character : u64) -> Value {
+    json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+}
+
+#[test]
+fn reports_unclosed_brackets() {
+    let (mut client, diagnostics) = Client::open();
+    assert_eq!(diagnostics["method"], "textDocument/publishDiagnostics");
+    let problems = diagnostics["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0]["range"]["start"], json!({ "line": 2, "character": 0 }));
+    assert_eq!(problems[0]["code"], "IncompleteList");
+
+    client.send(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": { "textDocument": { "uri": URI, "version": 2 }, "contentChanges": [{ "text": "\" hello } \" }" }] },
+    }));
+    let diagnostics = client.receive();
+    let problems = diagnostics["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0]["range"]["start"]["character"], 12);
+    assert!(client.close());
+}
+
+#[test]
+fn hovers_builtins_and_functions() {
+    let (mut client, _) = Client::open();
+    let hover = client.request(2, "textDocument/hover", position(0, 8));
+    let text = hover["result"]["contents"]["value"].as_str().unwrap();
+    assert!(text.contains("( x y -- x+y )"), "{}", text);
+
+    let hover = client.request(3, "textDocument/hover", position(1, 3));
+    let text = hover["result"]["contents"]["value"].as_str().unwrap();
+    assert!(text.contains("line 1"), "{}", text);
+
+    let hover = client.request(4, "textDocument/hover", position(1, 0));
+    assert!(hover["result"].is_null());
+    assert!(client.close());
+}
+
+#[test]
+fn completes_builtins_and_functions() {
+    let (mut client, _) = Client::open();
+    let completion = client.request(2, "textDocument/completion", position(1, 0));
+    let labels : Vec<&str> = completion["result"].as_array().unwrap().iter().map(|item| item["label"].as_str().unwrap()).collect();
+    for label in ["map", "foldl", "inc", "sum"] {
+        assert!(labels.contains(&label), "{} missing from {:?}", label, labels);
+    }
+    assert!(client.close());
+}
+
+#[test]
+fn goes_to_function_definitions() {
+    let (mut client, _) = Client::open();
+    let location = client.request(2, "textDocument/definition", position(1, 4));
+    assert_eq!(location["result"]["uri"], URI);
+    assert_eq!(location["result"]["range"]["start"], json!({ "line": 0, "character": 0 }));
+
+    let location = client.request(3, "textDocument/definition", position(0, 8));
+    assert!(location["result"].is_null());
+    assert!(client.close());
+}
+
+#[test]
+fn rejects_unknown_requests() {
+    let mut client = Client::start();
+    let response = client.request(1, "textDocument/rename", json!({}));
+    assert_eq!(response["error"]["code"], -32601);
+    assert!(client.close());
+}
+
+#[test]
+fn answers_malformed_messages_and_keeps_serving() {
+    let mut client = Client::start();
+    client.send_body(b"{ \"jsonrpc\": \"2.0\", \"id\": 1, ");
+    let response = client.receive();
+    assert_eq!(response["error"]["code"], -32700);
+    assert!(response["id"].is_null());
+
+    let response = client.request(2, "initialize", json!({ "capabilities": {} }));
+    assert_eq!(response["result"]["serverInfo"]["name"], "bprog-lsp");
+    assert!(client.close());
+}
+
+#[test]
+fn answers_headers_without_a_length_and_keeps_serving() {
+    let mut client = Client::start();
+    for headers in ["Content-Length: abc\r\n\r\n", "Content-Type: application/json\r\n\r\n", "X-Content-Length: 2\r\n\r\n"] {
+        //The body mentions the header, which is not taken for the start of the next message
+        write!(client.stdin, "{}{}", headers, json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "note": "Content-Length: 2" } })).unwrap();
+        let response = client.receive();
+        assert_eq!(response["error"]["code"], -32600);
+        assert!(response["id"].is_null());
+    }
+
+    let response = client.request(2, "initialize", json!({ "capabilities": {} }));
+    assert_eq!(response["result"]["serverInfo"]["name"], "bprog-lsp");
+    assert!(client.close());
+}
+
+#[test]
+fn reads_header_names_in_any_case() {
+    let mut client = Client::start();
+    let body = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "capabilities": {} } }).to_string();
+    write!(client.stdin, "content-length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    let response = client.receive();
+    assert_eq!(response["result"]["serverInfo"]["name"], "bprog-lsp");
+    assert!(client.close());
+}
+
+#[test]
+fn skips_messages_above_the_size_limit() {
+    let mut client = Client::start();
+    client.send_body(&vec![b' '; (16 << 20) + 1]);
+    let response = client.receive();
+    assert_eq!(response["error"]["code"], -32600);
+    assert!(response["id"].is_null());
+
+    let response = client.request(2, "initialize", json!({ "capabilities": {} }));
+    assert_eq!(response["result"]["serverInfo"]["name"], "bprog-lsp");
+    assert!(client.close());
+}
@@ -0,0 +1,14 @@
+//Builds the interpreter core with the `std` feature off, the way a host without std would use it
+use std::process::Command;
+
+#[test]
+fn core_builds_without_std() {
+    let output = Command::new(env!("CARGO"))
+        .args(["build", "--lib", "--no-default-features", "--manifest-path", concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")])
+        //A target directory of its own, so the build does not wait for the lock held by `cargo test`
+        .env("CARGO_TARGET_DIR", concat!(env!("CARGO_TARGET_TMPDIR"), "/no-std"))
+        .output()
+        .expect("cargo could not be started");
+
+    assert!(output.status.success(), "the core does not build without std:\n{}", String::from_utf8_lossy(&output.stderr));
+}
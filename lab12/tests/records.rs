@@ -0,0 +1,68 @@
+//Printing records, with and without a `show` for their type
+use std::path::PathBuf;
+
+use lab12::{format_stack_item, Datatype, Interpreter};
+
+fn interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.search_path.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test-modules"));
+    interpreter
+}
+
+fn printed(interpreter : &mut Interpreter, program : &str) -> String {
+    let value = interpreter.run(program).unwrap();
+    interpreter.format(value)
+}
+
+#[test]
+fn records_print_as_a_literal_that_reads_back_anywhere() {
+    let mut interpreter = interpreter();
+    interpreter.execute("point [ x y ] struct").unwrap();
+    let printed = printed(&mut interpreter, "1 [ 2/3 \" a \" ] point");
+    assert_eq!(printed, "( 1 [ 2/3 \" a \" ] 'point [ x y ] record )");
+
+    let value = interpreter.run("1 [ 2/3 \" a \" ] point").unwrap();
+    assert_eq!(Interpreter::new().run(&printed).unwrap(), value);
+    assert_eq!(Interpreter::new().run(&format!("[ {} ] head", printed)).unwrap(), value);
+}
+
+#[test]
+fn show_gives_the_printed_text() {
+    let mut interpreter = interpreter();
+    interpreter.execute("card [ rank ] struct card.show { card.rank case { 1 { \" ace \" } 13 { \" king \" } _ { pop \" card \" } } } fun").unwrap();
+    assert_eq!(printed(&mut interpreter, "1 card"), "ace");
+    assert_eq!(printed(&mut interpreter, "13 card 5 card [ ] cons cons"), "[ king card ]");
+    //Only the interpreter knows the functions, the free function prints the record as usual
+    assert_eq!(format_stack_item(interpreter.run("1 card").unwrap()), "( 1 'card [ rank ] record )");
+}
+
+#[test]
+fn show_that_fails_or_gives_no_string_is_ignored() {
+    let mut interpreter = interpreter();
+    interpreter.execute("a [ x ] struct a.show { a.x } fun b [ x ] struct b.show { fun } fun").unwrap();
+    assert_eq!(printed(&mut interpreter, "1 a"), "( 1 'a [ x ] record )");
+    assert_eq!(printed(&mut interpreter, "1 b"), "( 1 'b [ x ] record )");
+}
+
+#[test]
+fn imported_structs_keep_their_namespace() {
+    let mut interpreter = interpreter();
+    interpreter.execute("import geometry.bprog").unwrap();
+    let sum = interpreter.run("1 2 geometry.vec 3 4 geometry.vec +").unwrap();
+    assert_eq!(format_stack_item(sum.clone()), "( 4 6 'geometry.vec [ x y ] record )");
+    assert_eq!(interpreter.format(sum), "off the y axis");
+    assert_eq!(printed(&mut interpreter, "0 1 geometry.vec"), "on the y axis");
+    assert_eq!(interpreter.run("3 4 geometry.vec geometry.vec.y").unwrap(), Datatype::Int(4));
+}
+
+#[test]
+fn records_survive_a_snapshot() {
+    let mut interpreter = interpreter();
+    interpreter.execute("point [ x y ] struct point.+ { | a b | a point.x b point.x + a point.y b point.y + point } fun 1 2 point").unwrap();
+
+    let mut restored = Interpreter::new();
+    restored.restore(&interpreter.snapshot()).unwrap();
+    assert_eq!(restored.stack, interpreter.stack);
+    restored.execute("3 4 point + point.y").unwrap();
+    assert_eq!(restored.stack, vec![Datatype::Int(6)]);
+}
@@ -0,0 +1,148 @@
+//Runs the evaluation server on a free localhost port and talks plain HTTP to it
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+
+use lab12::server::{serve, DEFAULT_LIMITS};
+use lab12::Limits;
+use serde_json::{json, Value};
+
+fn start(limits : Limits) -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    listener.set_nonblocking(true).unwrap();
+
+    thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            serve(listener, limits).await.unwrap();
+        });
+    });
+    address
+}
+
+fn post(address : SocketAddr, body : &Value) -> (u16, Value) {
+    let body = body.to_string();
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "POST /eval HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[test]
+fn returns_the_final_stack() {
+    let address = start(DEFAULT_LIMITS);
+    assert_eq!(post(address, &json!({ "source": "1 2 + [ 1 2 ] map { 2.5 * }" })), (200, json!({ "stack": [3, [2.5, 5.0]] })));
+}
+
+#[test]
+fn starts_from_the_given_stack() {
+    let address = start(DEFAULT_LIMITS);
+    let request = json!({ "source": "exec swap", "stack": ["a", true, { "code": "not" }] });
+    assert_eq!(post(address, &request), (200, json!({ "stack": [false, "a"] })));
+
+    let request = json!({ "source": "1 +", "stack": [{ "int": "170141183460469231731687303715884105726" }] });
+    assert_eq!(post(address, &request), (200, json!({ "stack": [{ "int": "170141183460469231731687303715884105727" }] })));
+}
+
+#[test]
+fn reports_program_errors() {
+    let address = start(DEFAULT_LIMITS);
+    let (status, body) = post(address, &json!({ "source": "1 2 [ 3 ] +" }));
+    assert_eq!(status, 422);
+    assert_eq!(body, json!({ "error": "ExpectedNumber", "stack": [1] }));
+}
+
+#[test]
+fn rejects_values_without_a_bprog_type() {
+    let address = start(DEFAULT_LIMITS);
+    let (status, body) = post(address, &json!({ "source": "", "stack": [1, null] }));
+    assert_eq!(status, 400);
+    assert_eq!(body["error"], "InvalidStack");
+}
+
+#[test]
+fn stops_programs_at_the_limits() {
+    let address = start(Limits { steps: 1000, stack: 100, depth: 16, length: 100 });
+    for source in ["loop { loop } fun loop", "1000 times { 1 }", "1000 times { 1 pop }"] {
+        let (status, body) = post(address, &json!({ "source": source }));
+        assert_eq!((status, &body["error"]), (422, &json!("LimitExceeded")), "{}", source);
+    }
+}
+
+#[test]
+fn stops_deep_recursion_before_the_thread_overflows() {
+    let address = start(DEFAULT_LIMITS);
+    let (status, body) = post(address, &json!({ "source": "deeper { 1 + deeper } fun 0 deeper" }));
+    assert_eq!((status, &body["error"]), (422, &json!("LimitExceeded")));
+}
+
+#[test]
+fn stops_values_from_growing_past_the_limits() {
+    let address = start(DEFAULT_LIMITS);
+    for source in ["[ 1 ] 40 times { dup append }", "[ [ 1 ] ] 30 times { dup [ ] cons append }", "[ 1 2 3 4 5 6 7 8 9 10 ] { | xs | 100000 times { xs pop } } exec"] {
+        let (status, body) = post(address, &json!({ "source": source }));
+        assert_eq!((status, &body["error"]), (422, &json!("LimitExceeded")), "{}", source);
+    }
+
+    let address = start(Limits { length: 1000, ..DEFAULT_LIMITS });
+    let (status, body) = post(address, &json!({ "source": "[ ] 2000 times { 1 swap cons }" }));
+    assert_eq!((status, &body["error"]), (422, &json!("LimitExceeded")));
+}
+
+#[test]
+fn refuses_nesting_too_deep_and_keeps_serving() {
+    let address = start(DEFAULT_LIMITS);
+    for source in ["[ ".repeat(200_000), "{ ".repeat(200_000), "[ ] 1000 times { [ ] cons }".to_string()] {
+        let (status, body) = post(address, &json!({ "source": source }));
+        assert_eq!((status, &body["error"]), (422, &json!("NestingTooDeep")));
+    }
+    assert_eq!(post(address, &json!({ "source": "1 2 +" })), (200, json!({ "stack": [3] })));
+}
+
+#[test]
+fn counts_the_steps_of_parallel_workers() {
+    let address = start(Limits { steps: 1000, stack: 100, depth: 16, length: 100 });
+    let (status, _) = post(address, &json!({ "source": "[ 1 2 3 ] pmap { 100 times { 1 pop } }" }));
+    assert_eq!(status, 200);
+    for source in ["[ 1 2 3 4 5 6 7 8 ] pmap { 100 times { 1 pop } }", "[ 1 2 3 4 5 6 7 8 ] pfilter { 100 times { 1 pop } True }", "[ 1 2 3 ] pmap { 100 times { 1 pop } } 100 times { 1 pop }"] {
+        let (status, body) = post(address, &json!({ "source": source }));
+        assert_eq!((status, &body["error"]), (422, &json!("LimitExceeded")), "{}", source);
+    }
+}
+
+#[test]
+fn runs_nested_parallel_calls_on_the_outer_workers() {
+    let address = start(DEFAULT_LIMITS);
+    let list = "[ 1 2 3 4 5 6 7 8 ]";
+    let source = format!("{0} pmap {{ {0} pmap {{ {0} pmap {{ {0} pfilter {{ 4 > }} length }} }} }} head head", list);
+    assert_eq!(post(address, &json!({ "source": source })), (200, json!({ "stack": [[4, 4, 4, 4, 4, 4, 4, 4]] })));
+}
+
+#[test]
+fn charges_comparisons_and_moved_elements_of_large_lists() {
+    let address = start(DEFAULT_LIMITS);
+    let list = "0 iterate { 1 + } 100000 take toList dup append";
+    assert_eq!(post(address, &json!({ "source": format!("{} length", list) })), (200, json!({ "stack": [200000] })));
+    for source in [format!("{} dedup length", list), format!("{} 190000 times {{ tail }} length", list), format!("{} 190000 times {{ case {{ [ h | t ] {{ t }} }} }} length", list)] {
+        let (status, body) = post(address, &json!({ "source": source }));
+        assert_eq!((status, &body["error"]), (422, &json!("LimitExceeded")), "{}", source);
+    }
+    let (status, body) = post(address, &json!({ "source": "[ 3 1 3 2 1 ] dedup" }));
+    assert_eq!((status, body), (200, json!({ "stack": [[3, 1, 2]] })));
+}
+
+#[test]
+fn refuses_to_import_files() {
+    let address = start(DEFAULT_LIMITS);
+    let module = concat!(env!("CARGO_MANIFEST_DIR"), "/test-modules/shapes.bprog");
+    for source in [format!("import \" {} \" 3 shapes.square", module), "import \" /etc/passwd \"".to_string(), "import \" /nonexistent \"".to_string()] {
+        let (status, body) = post(address, &json!({ "source": source }));
+        assert_eq!((status, &body["error"]), (422, &json!("ImportRefused")), "{}", source);
+    }
+}
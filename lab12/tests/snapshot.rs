@@ -0,0 +1,75 @@
+//Saving the state of an interpreter and restoring it into another one
+use lab12::{format_stack_item, Interpreter, ProgramError, SNAPSHOT_VERSION};
+
+fn stack(interpreter : &Interpreter) -> String {
+    let items : Vec<String> = interpreter.stack.iter().map(|item| format_stack_item(item.clone())).collect();
+    items.join(" ")
+}
+
+fn restored(snapshot : &str) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.restore(snapshot).unwrap();
+    interpreter
+}
+
+#[test]
+fn stack_values_of_every_kind_come_back_equal() {
+    let mut interpreter = Interpreter::new();
+    let program = "170141183460469231731687303715884105727 NaN -0.0 2/3 True \" a  \\\"b\\\"\\n \" [ 1 [ 'x ] { 1 + } ] 1 iterate { 2 * } filter { 3 > } 3 take";
+    interpreter.execute(program).unwrap();
+
+    let restored = restored(&interpreter.snapshot());
+    assert_eq!(restored.stack, interpreter.stack);
+    assert_eq!(stack(&restored), stack(&interpreter));
+}
+
+#[test]
+fn functions_and_closures_keep_working() {
+    let mut interpreter = Interpreter::new();
+    interpreter.execute("adder { | n | { n + } } fun 'add5 5 adder fun 10 { | k | { k * } } exec").unwrap();
+
+    let mut restored = restored(&interpreter.snapshot());
+    restored.execute("2 add5 swap 3 swap exec").unwrap();
+    assert_eq!(stack(&restored), "7 30");
+}
+
+#[test]
+fn precision_is_kept() {
+    let mut interpreter = Interpreter::new();
+    interpreter.execute("2 precision").unwrap();
+
+    let restored = restored(&interpreter.snapshot());
+    assert_eq!(restored.format(lab12::Datatype::Float(1.0 / 3.0)), "0.33");
+}
+
+#[test]
+fn restoring_replaces_the_state() {
+    let mut interpreter = Interpreter::new();
+    interpreter.execute("1 2").unwrap();
+    let snapshot = interpreter.snapshot();
+
+    let mut other = Interpreter::new();
+    other.execute("3 f { 4 } fun").unwrap();
+    other.restore(&snapshot).unwrap();
+    assert_eq!(stack(&other), "1 2");
+    assert!(matches!(other.execute("f"), Err(ProgramError::UnknownWord)));
+}
+
+#[test]
+fn other_versions_are_rejected() {
+    let snapshot = Interpreter::new().snapshot();
+    let newer = snapshot.replace(&format!("\"version\": {}", SNAPSHOT_VERSION), &format!("\"version\": {}", SNAPSHOT_VERSION + 1));
+    assert_ne!(newer, snapshot);
+    assert!(matches!(Interpreter::new().restore(&newer), Err(ProgramError::UnsupportedSnapshotVersion)));
+}
+
+#[test]
+fn malformed_snapshots_leave_the_interpreter_as_it_was() {
+    let mut interpreter = Interpreter::new();
+    interpreter.execute("1").unwrap();
+    for snapshot in ["", "{}", "[1, 2]", r#"{ "format": "bprog-snapshot", "version": 1 }"#,
+        r#"{ "format": "bprog-snapshot", "version": 1, "precision": null, "stack": [{ "int": "x" }], "locals": [], "functions": [], "imported": [] }"#] {
+        assert!(matches!(interpreter.restore(snapshot), Err(ProgramError::InvalidSnapshot)), "{}", snapshot);
+    }
+    assert_eq!(stack(&interpreter), "1");
+}
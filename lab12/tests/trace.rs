@@ -0,0 +1,39 @@
+//Tracing and profiling of the words a program runs
+use lab12::trace::{Tracer, MAX_EVENTS};
+use lab12::Interpreter;
+use serde_json::Value;
+
+#[test]
+fn nothing_is_recorded_when_off() {
+    let mut interpreter = Interpreter::new();
+    interpreter.run("[ 1 2 3 ] map { 1 + } sum").unwrap();
+    assert_eq!(interpreter.tracer.report().lines().count(), 1);
+    assert_eq!(serde_json::from_str::<Value>(&interpreter.tracer.chrome_trace()).unwrap()["traceEvents"], Value::Array(Vec::new()));
+}
+
+#[test]
+fn profiles_are_valid_chrome_traces() {
+    let mut interpreter = Interpreter::new();
+    interpreter.tracer = Tracer::new(false, true);
+    interpreter.run("a\"b\\c { 1 } fun a\"b\\c pop 1 2 +").unwrap();
+
+    let trace : Value = serde_json::from_str(&interpreter.tracer.chrome_trace()).unwrap();
+    let names : Vec<&str> = trace["traceEvents"].as_array().unwrap().iter().map(|event| event["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["a\"b\\c", "fun", "a\"b\\c", "pop", "+"]);
+    assert!(interpreter.tracer.report().contains("pop"));
+}
+
+#[test]
+fn long_profiles_keep_counting_past_the_trace() {
+    let mut interpreter = Interpreter::new();
+    interpreter.tracer = Tracer::new(false, true);
+    interpreter.execute(&format!("{} times {{ 1 pop }}", MAX_EVENTS)).unwrap();
+
+    let trace : Value = serde_json::from_str(&interpreter.tracer.chrome_trace()).unwrap();
+    assert_eq!(trace["traceEvents"].as_array().unwrap().len(), MAX_EVENTS);
+    assert_eq!(trace["otherData"]["droppedEvents"], 1);
+    let report = interpreter.tracer.report();
+    let pop : Vec<&str> = report.lines().find(|line| line.starts_with("pop ")).unwrap().split_whitespace().collect();
+    assert_eq!(pop[1], MAX_EVENTS.to_string());
+    assert!(report.ends_with(&format!("1 more words are counted above but left out of the trace, which keeps the first {}\n", MAX_EVENTS)), "{}", report);
+}